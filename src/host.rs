@@ -0,0 +1,353 @@
+#![cfg(target_arch = "wasm32")]
+
+use std::{cell::Cell, rc::Rc};
+use glam::Vec2;
+use js_sys::{Function, Object, Reflect};
+use wasm_bindgen::{JsCast, JsValue};
+use web_sys::{
+    DedicatedWorkerGlobalScope, Event, EventTarget, HtmlCanvasElement, MessageEvent, MouseEvent,
+    OffscreenCanvas, Performance, Window, Worker,
+};
+
+use crate::input::keyboard;
+use crate::input::listener::Listener;
+use crate::render::gpu::Canvas;
+
+/// Event fields the input handlers read, copied into forwarded events.
+/// Offsets are computed separately since window events have them relative
+/// to whatever element is under the pointer.
+const FIELDS: [&str; 9] = [
+    "button",
+    "buttons",
+    "pointerId",
+    "pointerType",
+    "movementX",
+    "movementY",
+    "deltaY",
+    "deltaMode",
+    "code",
+];
+
+/// Where a viewer runs: on the page drawing into one of its canvases, or in
+/// a worker drawing into a transferred `OffscreenCanvas`. Input handlers
+/// listen on the targets given here, so they work the same in both.
+#[derive(Clone)]
+pub enum Host {
+    Page { window: Window, canvas: HtmlCanvasElement },
+    Worker(Rc<Remote>),
+}
+
+/// Worker side of a canvas whose input the page forwards with `forward`.
+pub struct Remote {
+    scope: DedicatedWorkerGlobalScope,
+    canvas: OffscreenCanvas,
+    /// Stand-ins for the canvas and the window, receiving the forwarded
+    /// events.
+    canvas_events: EventTarget,
+    window_events: EventTarget,
+    /// Size of the canvas on the page in CSS pixels.
+    size: Cell<Vec2>,
+    locked: Cell<bool>,
+}
+
+impl Host {
+    /// Hosts a viewer in the current worker, drawing into `canvas`.
+    pub fn worker(canvas: OffscreenCanvas) -> Result<Host, JsValue> {
+        let scope = js_sys::global().dyn_into::<DedicatedWorkerGlobalScope>()?;
+        let size = Vec2::new(canvas.width() as f32, canvas.height() as f32);
+        Ok(Host::Worker(Rc::new(Remote {
+            scope,
+            canvas,
+            canvas_events: EventTarget::new()?,
+            window_events: EventTarget::new()?,
+            size: Cell::new(size),
+            locked: Cell::new(false),
+        })))
+    }
+
+    /// Receives events on the canvas.
+    pub fn canvas_events(&self) -> &EventTarget {
+        match self {
+            Host::Page { canvas, .. } => canvas,
+            Host::Worker(remote) => &remote.canvas_events,
+        }
+    }
+
+    /// Receives events anywhere in the window, e.g. drags leaving the
+    /// canvas and keys.
+    pub fn window_events(&self) -> &EventTarget {
+        match self {
+            Host::Page { window, .. } => window,
+            Host::Worker(remote) => &remote.window_events,
+        }
+    }
+
+    /// The page's window; `None` in a worker, which has no URL hash or
+    /// gamepads of its own.
+    pub fn window(&self) -> Option<&Window> {
+        match self {
+            Host::Page { window, .. } => Some(window),
+            Host::Worker(_) => None,
+        }
+    }
+
+    /// Sets the size of the drawing buffer in device pixels.
+    pub fn set_canvas_size(&self, width: u32, height: u32) {
+        match self {
+            Host::Page { canvas, .. } => {
+                canvas.set_width(width);
+                canvas.set_height(height);
+            }
+            Host::Worker(remote) => {
+                remote.canvas.set_width(width);
+                remote.canvas.set_height(height);
+            }
+        }
+    }
+
+    pub fn render_canvas(&self) -> Canvas {
+        match self {
+            Host::Page { canvas, .. } => Canvas::Html(canvas.clone()),
+            Host::Worker(remote) => Canvas::Offscreen(remote.canvas.clone()),
+        }
+    }
+
+    /// Size of the canvas on the page in CSS pixels, the unit of pointer
+    /// offsets.
+    pub fn css_size(&self) -> Vec2 {
+        match self {
+            Host::Page { canvas, .. } => {
+                Vec2::new(canvas.client_width() as f32, canvas.client_height() as f32)
+            }
+            Host::Worker(remote) => remote.size.get(),
+        }
+    }
+
+    /// Pointer position relative to the canvas in CSS pixels. Unlike the
+    /// event offsets this also holds for window events, whose offsets are
+    /// relative to whatever element is under the pointer.
+    pub fn pointer_position(&self, e: &MouseEvent) -> Vec2 {
+        match self {
+            Host::Page { canvas, .. } => {
+                let rect = canvas.get_bounding_client_rect();
+                Vec2::new(
+                    (e.client_x() as f64 - rect.left()) as f32,
+                    (e.client_y() as f64 - rect.top()) as f32,
+                )
+            }
+            // `forward` already made the offsets canvas-relative.
+            Host::Worker(_) => Vec2::new(e.offset_x() as f32, e.offset_y() as f32),
+        }
+    }
+
+    pub fn is_canvas(&self, target: &EventTarget) -> bool {
+        target == self.canvas_events()
+    }
+
+    /// Whether the pointer is locked to the canvas.
+    pub fn is_pointer_locked(&self) -> bool {
+        match self {
+            Host::Page { window, canvas } => window
+                .document()
+                .and_then(|d| d.pointer_lock_element())
+                .is_some_and(|el| el.is_same_node(Some(canvas))),
+            Host::Worker(remote) => remote.locked.get(),
+        }
+    }
+
+    pub fn lock_pointer(&self) {
+        match self {
+            Host::Page { canvas, .. } => canvas.request_pointer_lock(),
+            Host::Worker(remote) => remote.post_lock(true),
+        }
+    }
+
+    /// Releases the pointer if it is locked to any element.
+    pub fn unlock_pointer(&self) {
+        match self {
+            Host::Page { window, .. } => {
+                if let Some(document) = window.document() {
+                    document.exit_pointer_lock();
+                }
+            }
+            Host::Worker(remote) => remote.post_lock(false),
+        }
+    }
+
+    pub fn performance(&self) -> Option<Performance> {
+        match self {
+            Host::Page { window, .. } => window.performance(),
+            Host::Worker(remote) => remote.scope.performance(),
+        }
+    }
+
+    pub fn request_animation_frame(&self, callback: &Function) -> Option<i32> {
+        match self {
+            Host::Page { window, .. } => window.request_animation_frame(callback),
+            Host::Worker(remote) => remote.scope.request_animation_frame(callback),
+        }
+        .ok()
+    }
+
+    pub fn cancel_animation_frame(&self, id: i32) {
+        let _ = match self {
+            Host::Page { window, .. } => window.cancel_animation_frame(id),
+            Host::Worker(remote) => remote.scope.cancel_animation_frame(id),
+        };
+    }
+
+    /// Handles a message posted by `forward` or `post_size`. Events are
+    /// dispatched to the stand-in targets; a resize returns the new size of
+    /// the canvas in device pixels.
+    pub fn receive(&self, message: &JsValue) -> Option<(u32, u32)> {
+        let Host::Worker(remote) = self else {
+            return None;
+        };
+        let get = |key: &str| Reflect::get(message, &key.into()).unwrap_or_default();
+        match get("kind").as_string()?.as_str() {
+            "event" => {
+                let target = match get("target").as_string()?.as_str() {
+                    "window" => &remote.window_events,
+                    _ => &remote.canvas_events,
+                };
+                let event = Event::new(&get("type").as_string()?).ok()?;
+                // Event getters read properties by name, so the copied
+                // fields stand in for those of the original event.
+                let fields = get("event");
+                for key in Object::keys(fields.unchecked_ref()) {
+                    let value = Reflect::get(&fields, &key).unwrap_or_default();
+                    let _ = Reflect::set(&event, &key, &value);
+                }
+                let _ = target.dispatch_event(&event);
+                None
+            }
+            "resize" => {
+                let number = |key| get(key).as_f64().unwrap_or(0.0);
+                remote
+                    .size
+                    .set(Vec2::new(number("cssWidth") as f32, number("cssHeight") as f32));
+                Some((number("width") as u32, number("height") as u32))
+            }
+            "pointerlock" => {
+                remote.locked.set(get("locked").is_truthy());
+                None
+            }
+            _ => None,
+        }
+    }
+}
+
+impl Remote {
+    /// Asks the page to lock or release the pointer; it reports back with a
+    /// `pointerlock` message once that happened.
+    fn post_lock(&self, lock: bool) {
+        let message = object(&[("kind", "pointerlock".into()), ("lock", lock.into())]);
+        let _ = self.scope.post_message(&message);
+    }
+}
+
+fn object(entries: &[(&str, JsValue)]) -> Object {
+    let object = Object::new();
+    for (key, value) in entries {
+        let _ = Reflect::set(&object, &(*key).into(), value);
+    }
+    object
+}
+
+/// Sends the size of `canvas`, `width` x `height` device pixels, to the
+/// worker drawing into it.
+pub fn post_size(worker: &Worker, canvas: &HtmlCanvasElement, width: u32, height: u32) {
+    let message = object(&[
+        ("kind", "resize".into()),
+        ("width", width.into()),
+        ("height", height.into()),
+        ("cssWidth", canvas.client_width().into()),
+        ("cssHeight", canvas.client_height().into()),
+    ]);
+    let _ = worker.post_message(&message);
+}
+
+fn post_event(worker: &Worker, canvas: &HtmlCanvasElement, target: &str, e: &Event) {
+    let fields = Object::new();
+    for field in FIELDS {
+        let value = Reflect::get(e, &field.into()).unwrap_or_default();
+        if !value.is_undefined() {
+            let _ = Reflect::set(&fields, &field.into(), &value);
+        }
+    }
+    if let Some(e) = e.dyn_ref::<MouseEvent>() {
+        let rect = canvas.get_bounding_client_rect();
+        let _ = Reflect::set(&fields, &"offsetX".into(), &(e.client_x() as f64 - rect.left()).into());
+        let _ = Reflect::set(&fields, &"offsetY".into(), &(e.client_y() as f64 - rect.top()).into());
+    }
+    let message = object(&[
+        ("kind", "event".into()),
+        ("target", target.into()),
+        ("type", e.type_().into()),
+        ("event", fields.into()),
+    ]);
+    let _ = worker.post_message(&message);
+}
+
+/// Page side of a canvas transferred to `worker`: forwards its input and
+/// locks the pointer when the worker asks. Keys only go to the worker while
+/// the canvas has focus, see `keyboard::focus`. The size is sent separately
+/// with `post_size`.
+pub fn forward(window: &Window, canvas: &HtmlCanvasElement, worker: &Worker) -> Vec<Listener> {
+    let mut listeners = Vec::new();
+    let (focused, focus_listener) = keyboard::focus(window, canvas);
+    listeners.push(focus_listener);
+
+    for event in ["pointerdown", "pointermove", "pointerleave", "pointerup", "wheel"] {
+        let (worker, canvas_c) = (worker.clone(), canvas.clone());
+        listeners.push(Listener::new(canvas, event, move |e: Event| {
+            // The worker cannot cancel the default actions: wheel scrolling
+            // and middle button auto-scrolling.
+            let middle = e.dyn_ref::<MouseEvent>().is_some_and(|e| e.button() == 1);
+            if e.type_() == "wheel" || (e.type_() == "pointerdown" && middle) {
+                e.prevent_default();
+            }
+            post_event(&worker, &canvas_c, "canvas", &e);
+        }));
+    }
+    listeners.push(Listener::new(canvas, "contextmenu", move |e: Event| e.prevent_default()));
+
+    for event in ["pointermove", "pointerup", "pointercancel", "keydown", "keyup"] {
+        let (worker, canvas_c, focused) = (worker.clone(), canvas.clone(), focused.clone());
+        listeners.push(Listener::new(window, event, move |e: Event| {
+            if e.type_() == "keydown" && !focused.get() {
+                return;
+            }
+            post_event(&worker, &canvas_c, "window", &e);
+        }));
+    }
+
+    if let Some(document) = window.document() {
+        let (worker, canvas_c) = (worker.clone(), canvas.clone());
+        listeners.push(Listener::new(&document.clone(), "pointerlockchange", move |_: Event| {
+            let locked = document
+                .pointer_lock_element()
+                .is_some_and(|el| el.is_same_node(Some(&canvas_c)));
+            let message = object(&[("kind", "pointerlock".into()), ("locked", locked.into())]);
+            let _ = worker.post_message(&message);
+        }));
+    }
+
+    {
+        let (window_c, canvas_c) = (window.clone(), canvas.clone());
+        listeners.push(Listener::new(worker, "message", move |e: MessageEvent| {
+            let data = e.data();
+            let kind = Reflect::get(&data, &"kind".into()).unwrap_or_default();
+            if kind.as_string().as_deref() != Some("pointerlock") {
+                return;
+            }
+            let lock = Reflect::get(&data, &"lock".into()).unwrap_or_default();
+            if lock.is_truthy() {
+                canvas_c.request_pointer_lock();
+            } else if let Some(document) = window_c.document() {
+                document.exit_pointer_lock();
+            }
+        }));
+    }
+    listeners
+}
@@ -1,17 +1,43 @@
+use crate::input::arcball_camera::ArcballCamera;
+use crate::input::bindings::Action;
 use crate::input::camera::{Camera, CameraController};
+pub use crate::input::camera_state::CameraType;
+use crate::input::camera_state::{CameraState, OrbitState};
+use crate::input::gamepad::AnalogInput;
+use crate::input::inertia::Inertia;
 use crate::input::orbit_camera::OrbitCamera;
-use glam::{Mat4, Vec3};
+use crate::input::projection::Lens;
+use crate::input::view_cube::StandardView;
+use crate::input::walk_camera::WalkCamera;
+use crate::scene::bounds::Aabb;
+use crate::scene::collision::{Collider, Triangle};
+use crate::scene::world::WorldFrame;
+use glam::{Mat4, Quat, Vec2, Vec3};
+use std::rc::Rc;
 
-#[derive(Copy, Clone)]
-pub enum CameraType {
-    Free,
-    Orbit,
+/// Duration of the blend between cameras after a mode switch, in seconds.
+const SWITCH_DURATION: f32 = 0.3;
+
+/// Pose of the outgoing camera, blended into the incoming one.
+struct Blend {
+    position: Vec3,
+    rotation: Quat,
+    elapsed: f32,
 }
 
 pub struct ActiveCamera {
     free: Camera,
     orbit: OrbitCamera,
+    arcball: ArcballCamera,
+    walk: WalkCamera,
     active: CameraType,
+    blend: Option<Blend>,
+    /// The cameras move in the Y-up display frame; this maps the world into
+    /// it so up is the world's up axis.
+    frame: WorldFrame,
+    /// World-space geometry the walk camera collides with, kept to rebuild
+    /// its collider when the frame changes.
+    collision: Rc<[Triangle]>,
 }
 
 impl ActiveCamera {
@@ -19,23 +45,160 @@ impl ActiveCamera {
         Self {
             free: Camera::new(aspect),
             orbit: OrbitCamera::new(aspect),
+            arcball: ArcballCamera::new(aspect),
+            walk: WalkCamera::new(aspect),
             active: CameraType::Orbit,
+            blend: None,
+            frame: WorldFrame::default(),
+            collision: Rc::from([]),
         }
     }
 
+    pub fn world_frame(&self) -> WorldFrame {
+        self.frame
+    }
+
+    /// Changes the world convention. Camera poses are kept on screen, so the
+    /// scene appears to turn until its up axis points up.
+    pub fn set_world_frame(&mut self, frame: WorldFrame) {
+        self.frame = frame;
+        self.set_collision(self.collision.clone());
+    }
+
+    pub fn camera_type(&self) -> CameraType {
+        self.active
+    }
+
+    /// Switches cameras. The incoming camera takes over the current pose and
+    /// the view is blended from where it was over `SWITCH_DURATION`.
     pub fn set_type(&mut self, ty: CameraType) {
+        if ty == self.active {
+            return;
+        }
+        let distance = self.distance();
+        let (rotation, position) = self.start_blend();
+        let forward = rotation * Vec3::Z;
+        match ty {
+            CameraType::Free => self.free.look_from(position, forward, distance),
+            CameraType::Orbit => self.orbit.look_from(position, forward, distance),
+            CameraType::Arcball => self.arcball.look_from(position, rotation, distance),
+            CameraType::Walk => self.walk.look_from(position, forward, distance),
+        }
         self.active = ty;
     }
 
+    pub fn state(&self) -> CameraState {
+        CameraState {
+            camera: self.active,
+            free: self.free.state(),
+            orbit: self.orbit.state(),
+            arcball: self.arcball.state(),
+            walk: self.walk.state(),
+        }
+    }
+
+    /// Restores a state from `state()`, optionally blending the view from
+    /// where it is.
+    pub fn set_state(&mut self, state: &CameraState, animate: bool) {
+        if animate {
+            self.start_blend();
+        } else {
+            self.blend = None;
+        }
+        self.free.set_state(&state.free);
+        self.orbit.set_state(&state.orbit);
+        self.arcball.set_state(&state.arcball);
+        self.walk.set_state(&state.walk);
+        self.active = state.camera;
+    }
+
+    /// Starts blending from the current pose, which is returned.
+    fn start_blend(&mut self) -> (Quat, Vec3) {
+        let (_, rotation, position) = self.display_view().inverse().to_scale_rotation_translation();
+        self.blend = Some(Blend {
+            position,
+            rotation,
+            elapsed: 0.0,
+        });
+        (rotation, position)
+    }
+
     pub fn set_aspect(&mut self, aspect: f32) {
         self.free.set_aspect(aspect);
         self.orbit.set_aspect(aspect);
+        self.arcball.set_aspect(aspect);
+        self.walk.set_aspect(aspect);
+    }
+
+    /// Applies `f` to the lens of every camera, for settings that must not
+    /// depend on which camera is active.
+    pub fn update_lenses(&mut self, f: impl Fn(&mut Lens)) {
+        f(&mut self.free.lens);
+        f(&mut self.orbit.lens);
+        f(&mut self.arcball.lens);
+        f(&mut self.walk.lens);
+    }
+
+    pub fn set_inertia(&mut self, inertia: Inertia) {
+        self.free.inertia = inertia;
+        self.orbit.inertia = inertia;
+        self.arcball.inertia = inertia;
+        self.walk.inertia = inertia;
+    }
+
+    /// Replaces the world-space geometry the walk camera stands on.
+    pub fn set_collision(&mut self, triangles: Rc<[Triangle]>) {
+        let to_display = self.frame.to_display();
+        let display = triangles.iter().map(|t| t.transformed(to_display)).collect();
+        self.walk.set_collision(Collider::new(display));
+        self.collision = triangles;
+    }
+
+    pub fn collision(&self) -> Rc<[Triangle]> {
+        self.collision.clone()
+    }
+
+    /// Point the camera looks at in world space: the orbit target, or the
+    /// free camera's focus distance ahead.
+    pub fn target(&self) -> Vec3 {
+        let (rotation, position) = self.pose();
+        let target = position + rotation * Vec3::Z * self.distance();
+        self.frame.from_display().transform_point3(target)
+    }
+
+    fn distance(&self) -> f32 {
+        match self.active {
+            CameraType::Free => self.free.focus_distance,
+            CameraType::Orbit => self.orbit.radius(),
+            CameraType::Arcball => self.arcball.radius(),
+            CameraType::Walk => self.walk.focus_distance,
+        }
+    }
+
+    /// Puts the orbit camera at world `position` looking at `target`,
+    /// without blending. Used to drive the camera from a path.
+    pub fn look_at(&mut self, position: Vec3, target: Vec3, fov_y: f32) {
+        self.active = CameraType::Orbit;
+        self.blend = None;
+        let to_display = self.frame.to_display();
+        let position = to_display.transform_point3(position);
+        let offset = to_display.transform_point3(target) - position;
+        self.orbit.look_from(position, offset.normalize_or(Vec3::Z), offset.length());
+        self.orbit.lens.fov_y = fov_y;
+    }
+
+    /// Switches to the orbit camera and moves it to show world `bounds`.
+    pub fn frame_bounds(&mut self, bounds: &Aabb) {
+        self.set_type(CameraType::Orbit);
+        self.orbit.frame_bounds(&bounds.transformed(self.frame.to_display()));
     }
 
     fn active_mut(&mut self) -> &mut dyn CameraController {
         match self.active {
             CameraType::Free => &mut self.free,
             CameraType::Orbit => &mut self.orbit,
+            CameraType::Arcball => &mut self.arcball,
+            CameraType::Walk => &mut self.walk,
         }
     }
 
@@ -43,32 +206,118 @@ impl ActiveCamera {
         match self.active {
             CameraType::Free => &self.free,
             CameraType::Orbit => &self.orbit,
+            CameraType::Arcball => &self.arcball,
+            CameraType::Walk => &self.walk,
+        }
+    }
+
+    /// Camera-to-world pose, part way between the outgoing and the active
+    /// camera while a switch is blending.
+    fn pose(&self) -> (Quat, Vec3) {
+        let (_, rotation, position) = self.active_ref().view().inverse().to_scale_rotation_translation();
+        match &self.blend {
+            Some(blend) => {
+                let t = (blend.elapsed / SWITCH_DURATION).min(1.0);
+                let t = t * t * (3.0 - 2.0 * t);
+                (blend.rotation.slerp(rotation, t), blend.position.lerp(position, t))
+            }
+            None => (rotation, position),
         }
     }
 }
 
 impl CameraController for ActiveCamera {
-    fn key_down(&mut self, code: String) {
-        self.active_mut().key_down(code);
+    fn action_down(&mut self, action: Action) {
+        self.active_mut().action_down(action);
     }
 
-    fn key_up(&mut self, code: String) {
-        self.active_mut().key_up(code);
+    fn action_up(&mut self, action: Action) {
+        self.active_mut().action_up(action);
     }
 
     fn mouse_move(&mut self, dx: f32, dy: f32) {
         self.active_mut().mouse_move(dx, dy);
     }
 
+    fn pan(&mut self, dx: f32, dy: f32) {
+        self.active_mut().pan(dx, dy);
+    }
+
+    fn zoom(&mut self, amount: f32, ndc: Vec2) {
+        self.active_mut().zoom(amount, ndc);
+    }
+
+    fn analog(&mut self, input: &AnalogInput, dt: f32) {
+        self.active_mut().analog(input, dt);
+    }
+
+    fn drag_end(&mut self) {
+        self.active_mut().drag_end();
+    }
+
+    fn is_animating(&self) -> bool {
+        self.blend.is_some() || self.active_ref().is_animating()
+    }
+
+    fn wants_pointer_lock(&self) -> bool {
+        self.active_ref().wants_pointer_lock()
+    }
+
+    fn snap_to_view(&mut self, view: StandardView) {
+        if matches!(self.active, CameraType::Free | CameraType::Walk) {
+            // Turn around the point the orbit camera was looking at, not
+            // around one in front of the eye.
+            let target = self.orbit.state().target;
+            self.set_type(CameraType::Orbit);
+            let state = self.orbit.state();
+            self.orbit.set_state(&OrbitState { target, ..state });
+        }
+        self.active_mut().snap_to_view(view);
+    }
+
+    fn lens(&self) -> &Lens {
+        self.active_ref().lens()
+    }
+
+    fn lens_mut(&mut self) -> &mut Lens {
+        self.active_mut().lens_mut()
+    }
+
     fn update(&mut self, dt: f32) {
         self.active_mut().update(dt);
+        if let Some(blend) = &mut self.blend {
+            blend.elapsed += dt;
+            if blend.elapsed >= SWITCH_DURATION {
+                self.blend = None;
+            }
+        }
+    }
+
+    fn view(&self) -> Mat4 {
+        self.display_view() * self.frame.to_display()
     }
 
     fn matrix(&self) -> Mat4 {
-        self.active_ref().matrix()
+        let active = self.active_ref();
+        let matrix = if self.blend.is_none() {
+            active.matrix()
+        } else {
+            // Keep the active camera's projection, only the pose is blended.
+            let projection = active.matrix() * active.view().inverse();
+            projection * self.display_view()
+        };
+        matrix * self.frame.to_display()
     }
 
     fn position(&self) -> Vec3 {
-        self.active_ref().position()
+        self.frame.from_display().transform_point3(self.pose().1)
+    }
+
+    fn display_view(&self) -> Mat4 {
+        if self.blend.is_none() {
+            return self.active_ref().view();
+        }
+        let (rotation, position) = self.pose();
+        Mat4::from_rotation_translation(rotation, position).inverse()
     }
 }
@@ -0,0 +1,301 @@
+use glam::{Mat3, Mat4, Quat, Vec2, Vec3};
+use std::collections::HashSet;
+
+use crate::input::bindings::Action;
+use crate::input::camera::{CameraController, LOOK_RATE};
+use crate::input::camera_state::ArcballState;
+use crate::input::gamepad::AnalogInput;
+use crate::input::inertia::{
+    self, Inertia, DRAG_SMOOTHING, MIN_MOMENTUM, MIN_RADIUS, SNAP_DURATION, ZOOM_STEP,
+};
+use crate::input::projection::Lens;
+use crate::input::view_cube::StandardView;
+
+struct Transition {
+    from: Quat,
+    to: Quat,
+    elapsed: f32,
+}
+
+/// Camera-to-world rotation of a camera looking along `forward` with `up`
+/// towards the top of the screen.
+pub fn look_rotation(forward: Vec3, up: Vec3) -> Quat {
+    let forward = forward.normalize();
+    let right = up.cross(forward).normalize_or(Vec3::X);
+    Quat::from_mat3(&Mat3::from_cols(right, forward.cross(right), forward))
+}
+
+/// Rotates freely around a target. The orientation is a quaternion turned
+/// about the camera's own axes, so there is no pole to get stuck at and the
+/// camera can roll over the top of a model.
+pub struct ArcballCamera {
+    target: Vec3,
+    radius: f32,
+    /// Camera-to-world rotation; the camera looks along its local +Z.
+    rotation: Quat,
+    pub speed: f32,
+    pub sensitivity: f32,
+    pub lens: Lens,
+    pub inertia: Inertia,
+    pressed: HashSet<Action>,
+    aspect: f32,
+    transition: Option<Transition>,
+    velocity: Vec3,
+    zoom_velocity: f32,
+    /// Drag speed in pixels per second, kept as momentum after release.
+    angular_velocity: Vec2,
+    drag_delta: Vec2,
+    dragging: bool,
+}
+
+impl ArcballCamera {
+    pub fn new(aspect: f32) -> Self {
+        Self {
+            target: Vec3::ZERO,
+            radius: 3.0,
+            rotation: look_rotation(Vec3::NEG_X, Vec3::Y),
+            speed: 2.0,
+            sensitivity: 0.002,
+            lens: Lens::default(),
+            inertia: Inertia::default(),
+            pressed: HashSet::new(),
+            aspect,
+            transition: None,
+            velocity: Vec3::ZERO,
+            zoom_velocity: 0.0,
+            angular_velocity: Vec2::ZERO,
+            drag_delta: Vec2::ZERO,
+            dragging: false,
+        }
+    }
+
+    pub fn set_aspect(&mut self, aspect: f32) {
+        self.aspect = aspect;
+    }
+
+    /// Orbits around the point `distance` ahead of a camera at `position`
+    /// with `rotation`, keeping the view as it is. Drops any motion.
+    pub fn look_from(&mut self, position: Vec3, rotation: Quat, distance: f32) {
+        self.radius = distance.max(MIN_RADIUS);
+        self.rotation = rotation.normalize();
+        self.target = position + self.forward() * self.radius;
+        self.stop();
+    }
+
+    pub fn radius(&self) -> f32 {
+        self.radius
+    }
+
+    pub fn state(&self) -> ArcballState {
+        ArcballState {
+            target: self.target,
+            radius: self.radius,
+            rotation: self.rotation,
+            lens: self.lens,
+        }
+    }
+
+    pub fn set_state(&mut self, state: &ArcballState) {
+        self.target = state.target;
+        self.radius = state.radius.max(MIN_RADIUS);
+        self.rotation = state.rotation.normalize();
+        self.lens = Lens {
+            reverse_z: self.lens.reverse_z,
+            ..state.lens
+        };
+        self.stop();
+    }
+
+    fn stop(&mut self) {
+        self.transition = None;
+        self.velocity = Vec3::ZERO;
+        self.zoom_velocity = 0.0;
+        self.angular_velocity = Vec2::ZERO;
+    }
+
+    pub fn mouse_move(&mut self, dx: f32, dy: f32) {
+        self.transition = None;
+        self.dragging = true;
+        self.drag_delta += Vec2::new(dx, dy);
+        self.rotate(dx, dy);
+    }
+
+    pub fn drag_end(&mut self) {
+        self.dragging = false;
+        if self.inertia.rotation_damping <= 0.0 {
+            self.angular_velocity = Vec2::ZERO;
+        }
+    }
+
+    /// Turns about the camera's current up and right axes, so a drag always
+    /// moves the model the same way on screen whatever the orientation.
+    fn rotate(&mut self, dx: f32, dy: f32) {
+        let turn = Quat::from_rotation_y(dx * self.sensitivity) * Quat::from_rotation_x(dy * self.sensitivity);
+        self.rotation = (self.rotation * turn).normalize();
+    }
+
+    pub fn pan(&mut self, dx: f32, dy: f32) {
+        self.transition = None;
+        let scale = self.radius * (self.lens.fov_y * 0.5).tan();
+        self.target += (self.rotation * Vec3::Y * dy - self.rotation * Vec3::X * dx) * scale;
+    }
+
+    pub fn zoom(&mut self, amount: f32, ndc: Vec2) {
+        let radius = (self.radius * (-amount * ZOOM_STEP).exp()).max(MIN_RADIUS);
+        // Keep the point under the cursor on the focal plane in place.
+        let ray = CameraController::ray(self, ndc);
+        if let Some(t) = ray.intersect_plane(self.target, self.forward()) {
+            self.target += (ray.at(t) - self.target) * (1.0 - radius / self.radius);
+        }
+        self.radius = radius;
+    }
+
+    pub fn analog(&mut self, input: &AnalogInput, dt: f32) {
+        if input.look != Vec2::ZERO {
+            self.transition = None;
+        }
+        let (forward, right) = (self.forward(), self.rotation * Vec3::X);
+        self.target += (forward * input.movement.y + right * input.movement.x) * self.speed * dt;
+        let look = input.look * LOOK_RATE * dt / self.sensitivity;
+        self.rotate(look.x, look.y);
+        self.radius = (self.radius - input.zoom * self.speed * dt).max(MIN_RADIUS);
+    }
+
+    /// Starts an animated rotation around the target towards `view`.
+    pub fn snap_to(&mut self, view: StandardView) {
+        let (yaw, pitch) = view.angles();
+        let offset = Vec3::new(yaw.cos() * pitch.cos(), pitch.sin(), yaw.sin() * pitch.cos());
+        // Straight up or down views keep the current heading.
+        let up = if pitch.abs() > 1.5 { self.rotation * Vec3::Y } else { Vec3::Y };
+        let mut to = look_rotation(-offset, up);
+        // Take the shorter way round.
+        if self.rotation.dot(to) < 0.0 {
+            to = -to;
+        }
+        self.angular_velocity = Vec2::ZERO;
+        self.transition = Some(Transition {
+            from: self.rotation,
+            to,
+            elapsed: 0.0,
+        });
+    }
+
+    pub fn update(&mut self, dt: f32) {
+        if let Some(tr) = &mut self.transition {
+            tr.elapsed += dt;
+            let t = (tr.elapsed / SNAP_DURATION).min(1.0);
+            let t = t * t * (3.0 - 2.0 * t);
+            self.rotation = tr.from.slerp(tr.to, t);
+            if t >= 1.0 {
+                self.transition = None;
+            }
+        }
+
+        if self.dragging {
+            if dt > 0.0 {
+                self.angular_velocity =
+                    inertia::approach(self.angular_velocity, self.drag_delta / dt, DRAG_SMOOTHING, dt);
+            }
+        } else if self.angular_velocity != Vec2::ZERO {
+            let rate = self.inertia.rotation_damping;
+            let step = self.angular_velocity * inertia::coast(rate, dt);
+            self.rotate(step.x, step.y);
+            self.angular_velocity *= inertia::decay(rate, dt);
+            if self.angular_velocity.length() < MIN_MOMENTUM {
+                self.angular_velocity = Vec2::ZERO;
+            }
+        }
+        self.drag_delta = Vec2::ZERO;
+
+        let (forward, right) = (self.forward(), self.rotation * Vec3::X);
+        let wish = inertia::wish_velocity(&self.pressed, forward, right, self.speed);
+        let wish_zoom = inertia::wish_zoom(&self.pressed, self.speed);
+        let (rate, zoom_rate) = (self.inertia.rate(wish != Vec3::ZERO), self.inertia.rate(wish_zoom != 0.0));
+        self.velocity = inertia::approach(self.velocity, wish, rate, dt);
+        self.zoom_velocity = inertia::approach(self.zoom_velocity, wish_zoom, zoom_rate, dt);
+        self.target += self.velocity * dt;
+        self.radius = (self.radius + self.zoom_velocity * dt).max(MIN_RADIUS);
+    }
+
+    fn forward(&self) -> Vec3 {
+        self.rotation * Vec3::Z
+    }
+
+    pub fn position(&self) -> Vec3 {
+        self.target - self.forward() * self.radius
+    }
+
+    pub fn view(&self) -> Mat4 {
+        Mat4::from_rotation_translation(self.rotation, self.position()).inverse()
+    }
+
+    pub fn matrix(&self) -> Mat4 {
+        self.lens.matrix(self.aspect, self.radius) * self.view()
+    }
+}
+
+impl CameraController for ArcballCamera {
+    fn action_down(&mut self, action: Action) {
+        self.pressed.insert(action);
+    }
+
+    fn action_up(&mut self, action: Action) {
+        self.pressed.remove(&action);
+    }
+
+    fn mouse_move(&mut self, dx: f32, dy: f32) {
+        ArcballCamera::mouse_move(self, dx, dy);
+    }
+
+    fn pan(&mut self, dx: f32, dy: f32) {
+        ArcballCamera::pan(self, dx, dy);
+    }
+
+    fn zoom(&mut self, amount: f32, ndc: Vec2) {
+        ArcballCamera::zoom(self, amount, ndc);
+    }
+
+    fn is_animating(&self) -> bool {
+        self.transition.is_some()
+            || self.angular_velocity != Vec2::ZERO
+            || !self.pressed.is_empty()
+            || self.velocity.length() > inertia::REST_SPEED
+            || self.zoom_velocity.abs() > inertia::REST_SPEED
+    }
+
+    fn analog(&mut self, input: &AnalogInput, dt: f32) {
+        ArcballCamera::analog(self, input, dt);
+    }
+
+    fn drag_end(&mut self) {
+        ArcballCamera::drag_end(self);
+    }
+
+    fn snap_to_view(&mut self, view: StandardView) {
+        self.snap_to(view);
+    }
+
+    fn lens(&self) -> &Lens {
+        &self.lens
+    }
+
+    fn lens_mut(&mut self) -> &mut Lens {
+        &mut self.lens
+    }
+
+    fn update(&mut self, dt: f32) {
+        ArcballCamera::update(self, dt);
+    }
+
+    fn view(&self) -> Mat4 {
+        ArcballCamera::view(self)
+    }
+
+    fn matrix(&self) -> Mat4 {
+        ArcballCamera::matrix(self)
+    }
+
+    fn position(&self) -> Vec3 {
+        ArcballCamera::position(self)
+    }
+}
@@ -0,0 +1,99 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// Camera actions that keys can be bound to.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub enum Action {
+    MoveForward,
+    MoveBackward,
+    MoveLeft,
+    MoveRight,
+    ZoomIn,
+    ZoomOut,
+    Jump,
+}
+
+/// Maps `KeyboardEvent.code` values to actions. Serialized as an object of
+/// action names to lists of key codes, e.g. `{"MoveForward": ["KeyW"]}`.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct InputMap {
+    bindings: BTreeMap<Action, Vec<String>>,
+}
+
+impl Default for InputMap {
+    fn default() -> Self {
+        let defaults: [(Action, &[&str]); 7] = [
+            (Action::MoveForward, &["KeyW"]),
+            (Action::MoveBackward, &["KeyS"]),
+            (Action::MoveLeft, &["KeyA"]),
+            (Action::MoveRight, &["KeyD"]),
+            (Action::ZoomIn, &["Equal", "NumpadAdd"]),
+            (Action::ZoomOut, &["Minus", "NumpadSubtract"]),
+            (Action::Jump, &["Space"]),
+        ];
+        Self {
+            bindings: defaults
+                .into_iter()
+                .map(|(action, codes)| (action, codes.iter().map(|c| c.to_string()).collect()))
+                .collect(),
+        }
+    }
+}
+
+impl InputMap {
+    /// Action bound to the key `code`, if any.
+    pub fn action(&self, code: &str) -> Option<Action> {
+        self.bindings
+            .iter()
+            .find(|(_, codes)| codes.iter().any(|c| c == code))
+            .map(|(action, _)| *action)
+    }
+
+    /// Replaces the keys bound to `action`.
+    pub fn bind(&mut self, action: Action, codes: Vec<String>) {
+        self.bindings.insert(action, codes);
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).unwrap_or_default()
+    }
+
+    /// Parses bindings from JSON. Actions missing from `json` keep their
+    /// default keys.
+    pub fn from_json(json: &str) -> Result<Self, String> {
+        let parsed: BTreeMap<Action, Vec<String>> =
+            serde_json::from_str(json).map_err(|e| e.to_string())?;
+        let mut map = Self::default();
+        for (action, codes) in parsed {
+            map.bind(action, codes);
+        }
+        Ok(map)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn missing_actions_keep_their_defaults() {
+        let map = InputMap::from_json(r#"{"MoveForward": ["ArrowUp"]}"#).unwrap();
+        assert_eq!(map.action("ArrowUp"), Some(Action::MoveForward));
+        assert_eq!(map.action("KeyW"), None);
+        assert_eq!(map.action("KeyS"), Some(Action::MoveBackward));
+    }
+
+    #[test]
+    fn json_round_trips() {
+        let mut map = InputMap::default();
+        map.bind(Action::Jump, vec!["KeyJ".into()]);
+        assert_eq!(InputMap::from_json(&map.to_json()).unwrap(), map);
+    }
+
+    #[test]
+    fn unknown_actions_are_rejected() {
+        assert!(InputMap::from_json(r#"{"Fly": ["KeyF"]}"#).is_err());
+        assert!(InputMap::from_json("[]").is_err());
+    }
+}
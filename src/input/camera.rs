@@ -1,24 +1,88 @@
-use glam::{Mat4, Vec3};
+use glam::{Mat4, Vec2, Vec3};
 use std::collections::HashSet;
 
+use crate::input::bindings::Action;
+use crate::input::camera_state::FreeState;
+use crate::input::gamepad::AnalogInput;
+use crate::input::inertia::{self, Inertia};
+use crate::input::projection::Lens;
+use crate::input::view_cube::StandardView;
+use crate::scene::bounds::{Aabb, Ray};
+
 pub trait CameraController {
-    fn key_down(&mut self, code: String);
-    fn key_up(&mut self, code: String);
+    fn action_down(&mut self, action: Action);
+    fn action_up(&mut self, action: Action);
     fn mouse_move(&mut self, dx: f32, dy: f32);
+    /// Moves the view parallel to the image plane. Deltas are in normalized
+    /// device units, where 2.0 spans the viewport height.
+    fn pan(&mut self, _dx: f32, _dy: f32) {}
+    /// Dollies by `amount` wheel steps (positive = closer) towards the point
+    /// under `ndc`.
+    fn zoom(&mut self, _amount: f32, _ndc: Vec2) {}
+    /// Applies one frame of stick/trigger input.
+    fn analog(&mut self, _input: &AnalogInput, _dt: f32) {}
+    fn snap_to_view(&mut self, _view: StandardView) {}
+    /// Whether mouse look should lock the pointer instead of dragging.
+    fn wants_pointer_lock(&self) -> bool {
+        false
+    }
+    fn lens(&self) -> &Lens;
+    fn lens_mut(&mut self) -> &mut Lens;
 
     fn update(&mut self, dt: f32);
+    fn view(&self) -> Mat4;
     fn matrix(&self) -> Mat4;
     fn position(&self) -> Vec3;
+    /// View matrix from the Y-up display frame that `StandardView`s refer
+    /// to, which differs from `view` when the world is not Y-up.
+    fn display_view(&self) -> Mat4 {
+        self.view()
+    }
+
+    /// Part of the canvas the camera draws into, `[x, y, width, height]` as
+    /// fractions from the top left.
+    fn viewport(&self) -> [f32; 4] {
+        [0.0, 0.0, 1.0, 1.0]
+    }
+
+    /// World-space ray through `ndc` (normalized device coordinates, y up).
+    fn ray(&self, ndc: Vec2) -> Ray {
+        Ray::from_ndc(self.matrix(), ndc, self.lens().reverse_z)
+    }
+
+    /// Called when a rotation drag is released.
+    fn drag_end(&mut self) {}
+
+    /// Whether the camera keeps moving without further input (held keys,
+    /// momentum, transitions), so more frames need to be drawn.
+    fn is_animating(&self) -> bool {
+        false
+    }
+
+    /// Fits the near and far planes around `bounds` as seen from the camera.
+    fn fit_clip_planes(&mut self, bounds: &Aabb) {
+        let inv = self.view().inverse();
+        let (eye, forward) = (inv.w_axis.truncate(), inv.z_axis.truncate());
+        self.lens_mut().fit(eye, forward, bounds);
+    }
 }
 
+/// Turn rate at full stick deflection, in radians per second.
+pub const LOOK_RATE: f32 = 2.0;
+
 pub struct Camera {
     pub position: Vec3,
     yaw: f32,
     pitch: f32,
     pub speed: f32,
     pub sensitivity: f32,
-    pressed: HashSet<String>,
+    /// Distance whose extent the orthographic projection shows.
+    pub focus_distance: f32,
+    pub lens: Lens,
+    pub inertia: Inertia,
+    pressed: HashSet<Action>,
     aspect: f32,
+    velocity: Vec3,
 }
 
 impl Camera {
@@ -29,8 +93,12 @@ impl Camera {
             pitch: 0.0,
             speed: 2.0,
             sensitivity: 0.002,
+            focus_distance: 2.0,
+            lens: Lens::default(),
+            inertia: Inertia::default(),
             pressed: HashSet::new(),
             aspect,
+            velocity: Vec3::ZERO,
         }
     }
 
@@ -38,12 +106,44 @@ impl Camera {
         self.aspect = aspect;
     }
 
-    pub fn key_down(&mut self, code: String) {
-        self.pressed.insert(code);
+    /// Places the camera at `position` looking along `forward`, with the
+    /// orthographic extent of an object `distance` away. Drops any motion.
+    pub fn look_from(&mut self, position: Vec3, forward: Vec3, distance: f32) {
+        self.position = position;
+        self.yaw = forward.z.atan2(forward.x);
+        self.pitch = forward.y.clamp(-1.0, 1.0).asin().clamp(-1.54, 1.54);
+        self.focus_distance = distance;
+        self.velocity = Vec3::ZERO;
     }
 
-    pub fn key_up(&mut self, code: String) {
-        self.pressed.remove(&code);
+    pub fn state(&self) -> FreeState {
+        FreeState {
+            position: self.position,
+            yaw: self.yaw,
+            pitch: self.pitch,
+            focus_distance: self.focus_distance,
+            lens: self.lens,
+        }
+    }
+
+    pub fn set_state(&mut self, state: &FreeState) {
+        self.position = state.position;
+        self.yaw = state.yaw;
+        self.pitch = state.pitch.clamp(-1.54, 1.54);
+        self.focus_distance = state.focus_distance;
+        self.lens = Lens {
+            reverse_z: self.lens.reverse_z,
+            ..state.lens
+        };
+        self.velocity = Vec3::ZERO;
+    }
+
+    pub fn action_down(&mut self, action: Action) {
+        self.pressed.insert(action);
+    }
+
+    pub fn action_up(&mut self, action: Action) {
+        self.pressed.remove(&action);
     }
 
     pub fn mouse_move(&mut self, dx: f32, dy: f32) {
@@ -51,30 +151,44 @@ impl Camera {
         self.pitch = (self.pitch + dy * self.sensitivity).clamp(-1.54, 1.54);
     }
 
+    pub fn pan(&mut self, dx: f32, dy: f32) {
+        let inv = self.view().inverse();
+        let scale = self.focus_distance * (self.lens.fov_y * 0.5).tan();
+        self.position += (inv.y_axis.truncate() * dy - inv.x_axis.truncate() * dx) * scale;
+    }
+
+    pub fn zoom(&mut self, amount: f32, ndc: Vec2) {
+        let ray = CameraController::ray(self, ndc);
+        self.position += ray.direction * amount * self.speed * 0.25;
+    }
+
+    pub fn analog(&mut self, input: &AnalogInput, dt: f32) {
+        let forward = self.forward();
+        let right = Vec3::Y.cross(forward).normalize();
+        let step = input.movement.y + input.zoom;
+        self.position += (forward * step + right * input.movement.x) * self.speed * dt;
+        self.yaw -= input.look.x * LOOK_RATE * dt;
+        self.pitch = (self.pitch + input.look.y * LOOK_RATE * dt).clamp(-1.54, 1.54);
+    }
+
     pub fn update(&mut self, dt: f32) {
         let forward = self.forward();
         let right = Vec3::Y.cross(forward).normalize();
-        if self.pressed.contains("KeyW") {
-            self.position += forward * self.speed * dt;
-        }
-        if self.pressed.contains("KeyS") {
-            self.position -= forward * self.speed * dt;
-        }
-        if self.pressed.contains("KeyA") {
-            self.position -= right * self.speed * dt;
-        }
-        if self.pressed.contains("KeyD") {
-            self.position += right * self.speed * dt;
-        }
+        let wish = inertia::wish_velocity(&self.pressed, forward, right, self.speed);
+        let rate = self.inertia.rate(wish != Vec3::ZERO);
+        self.velocity = inertia::approach(self.velocity, wish, rate, dt);
+        self.position += self.velocity * dt;
+    }
+
+    pub fn view(&self) -> Mat4 {
+        Mat4::look_at_lh(self.position, self.position + self.forward(), Vec3::Y)
     }
 
     pub fn matrix(&self) -> Mat4 {
-        let view = Mat4::look_at_lh(self.position, self.position + self.forward(), Vec3::Y);
-        let proj = Mat4::perspective_lh(std::f32::consts::FRAC_PI_4, self.aspect, 0.1, 100.0);
-        proj * view
+        self.lens.matrix(self.aspect, self.focus_distance) * self.view()
     }
 
-fn forward(&self) -> Vec3 {
+    fn forward(&self) -> Vec3 {
         Vec3::new(
             self.yaw.cos() * self.pitch.cos(),
             self.pitch.sin(),
@@ -85,22 +199,54 @@ fn forward(&self) -> Vec3 {
 }
 
 impl CameraController for Camera {
-    fn key_down(&mut self, code: String) {
-        Camera::key_down(self, code);
+    fn action_down(&mut self, action: Action) {
+        Camera::action_down(self, action);
     }
 
-    fn key_up(&mut self, code: String) {
-        Camera::key_up(self, code);
+    fn action_up(&mut self, action: Action) {
+        Camera::action_up(self, action);
     }
 
     fn mouse_move(&mut self, dx: f32, dy: f32) {
         Camera::mouse_move(self, dx, dy);
     }
 
+    fn pan(&mut self, dx: f32, dy: f32) {
+        Camera::pan(self, dx, dy);
+    }
+
+    fn analog(&mut self, input: &AnalogInput, dt: f32) {
+        Camera::analog(self, input, dt);
+    }
+
+    fn wants_pointer_lock(&self) -> bool {
+        true
+    }
+
+    fn zoom(&mut self, amount: f32, ndc: Vec2) {
+        Camera::zoom(self, amount, ndc);
+    }
+
+    fn is_animating(&self) -> bool {
+        !self.pressed.is_empty() || self.velocity.length() > inertia::REST_SPEED
+    }
+
+    fn lens(&self) -> &Lens {
+        &self.lens
+    }
+
+    fn lens_mut(&mut self) -> &mut Lens {
+        &mut self.lens
+    }
+
     fn update(&mut self, dt: f32) {
         Camera::update(self, dt);
     }
 
+    fn view(&self) -> Mat4 {
+        Camera::view(self)
+    }
+
     fn matrix(&self) -> Mat4 {
         Camera::matrix(self)
     }
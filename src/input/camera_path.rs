@@ -0,0 +1,327 @@
+use glam::Vec3;
+use serde::{Deserialize, Serialize};
+use std::ops::{Add, Mul, Sub};
+
+#[cfg(target_arch = "wasm32")]
+use crate::render::data::Vertex;
+
+/// Line segments per keyframe interval in the debug polyline.
+#[cfg(target_arch = "wasm32")]
+const SEGMENTS_PER_KEY: usize = 16;
+#[cfg(target_arch = "wasm32")]
+const PATH_COLOR: [f32; 3] = [1.0, 0.8, 0.2];
+#[cfg(target_arch = "wasm32")]
+const TARGET_COLOR: [f32; 3] = [0.6, 0.5, 0.2];
+/// Half size of the cross marking a keyframe position.
+#[cfg(target_arch = "wasm32")]
+const MARKER_SIZE: f32 = 0.08;
+
+/// Camera pose at a point in time along a path.
+#[derive(Copy, Clone, Debug, Serialize, Deserialize)]
+pub struct Keyframe {
+    /// Seconds from the start of the path.
+    pub time: f32,
+    pub position: Vec3,
+    pub target: Vec3,
+    /// Vertical field of view in radians.
+    pub fov_y: f32,
+}
+
+impl Keyframe {
+    /// Rejects keyframes the camera cannot take: non-finite values, a field
+    /// of view outside (0, π) and a position on its target, which has no
+    /// view direction. Paths can be loaded from JSON, so they are not
+    /// trusted.
+    pub fn validate(&self) -> Result<(), String> {
+        if !(self.time.is_finite() && self.position.is_finite() && self.target.is_finite()) {
+            return Err("keyframe is not finite".into());
+        }
+        if !(self.fov_y > 0.0 && self.fov_y < std::f32::consts::PI) {
+            return Err("keyframe field of view must be between 0 and 180 degrees".into());
+        }
+        if self.position == self.target {
+            return Err("keyframe position equals its target".into());
+        }
+        Ok(())
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Interpolation {
+    /// Passes through every keyframe.
+    CatmullRom,
+    /// Uses the keyframes as control points of one Bezier curve, which is
+    /// smoother but only passes through the first and last keyframe.
+    Bezier,
+}
+
+/// Keyframed camera motion with playback state.
+pub struct CameraPath {
+    keyframes: Vec<Keyframe>,
+    pub interpolation: Interpolation,
+    pub looping: bool,
+    pub visible: bool,
+    time: f32,
+    playing: bool,
+    /// The pose at `time` has not been applied to the camera yet.
+    dirty: bool,
+}
+
+impl Default for CameraPath {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl CameraPath {
+    pub fn new() -> Self {
+        Self {
+            keyframes: Vec::new(),
+            interpolation: Interpolation::CatmullRom,
+            looping: false,
+            visible: true,
+            time: 0.0,
+            playing: false,
+            dirty: false,
+        }
+    }
+
+    pub fn keyframes(&self) -> &[Keyframe] {
+        &self.keyframes
+    }
+
+    /// Inserts a keyframe in time order, replacing one at the same time.
+    pub fn add(&mut self, key: Keyframe) {
+        match self.keyframes.iter().position(|k| k.time >= key.time) {
+            Some(i) if self.keyframes[i].time == key.time => self.keyframes[i] = key,
+            Some(i) => self.keyframes.insert(i, key),
+            None => self.keyframes.push(key),
+        }
+    }
+
+    pub fn set_keyframes(&mut self, mut keyframes: Vec<Keyframe>) {
+        keyframes.sort_by(|a, b| a.time.total_cmp(&b.time));
+        self.keyframes = keyframes;
+        self.seek(self.time);
+    }
+
+    pub fn clear(&mut self) {
+        self.keyframes.clear();
+        self.playing = false;
+        self.time = 0.0;
+    }
+
+    pub fn duration(&self) -> f32 {
+        self.keyframes.last().map_or(0.0, |k| k.time)
+    }
+
+    pub fn time(&self) -> f32 {
+        self.time
+    }
+
+    pub fn is_playing(&self) -> bool {
+        self.playing
+    }
+
+    /// Starts playback, from the beginning if the end was reached.
+    pub fn play(&mut self) {
+        if self.keyframes.is_empty() {
+            return;
+        }
+        if self.time >= self.duration() {
+            self.time = self.start();
+        }
+        self.playing = true;
+        self.dirty = true;
+    }
+
+    pub fn pause(&mut self) {
+        self.playing = false;
+    }
+
+    /// Jumps to `time`; the camera follows even while paused.
+    pub fn seek(&mut self, time: f32) {
+        self.time = time.clamp(self.start(), self.duration().max(self.start()));
+        self.dirty = !self.keyframes.is_empty();
+    }
+
+    /// Advances playback by `dt` and returns the pose the camera should take,
+    /// or `None` when the path does not drive the camera this frame.
+    pub fn update(&mut self, dt: f32) -> Option<Keyframe> {
+        if self.playing {
+            self.time += dt;
+            let (start, end) = (self.start(), self.duration());
+            if self.time >= end {
+                if self.looping && end > start {
+                    self.time = start + (self.time - start) % (end - start);
+                } else {
+                    self.time = end;
+                    self.playing = false;
+                }
+            }
+        } else if !self.dirty {
+            return None;
+        }
+        self.dirty = false;
+        self.sample(self.time)
+    }
+
+    /// Interpolated pose at `time`, clamped to the keyframe range.
+    pub fn sample(&self, time: f32) -> Option<Keyframe> {
+        let keys = &self.keyframes;
+        let (first, last) = (keys.first()?, keys.last()?);
+        if keys.len() == 1 || time <= first.time {
+            return Some(Keyframe { time, ..*first });
+        }
+        if time >= last.time {
+            return Some(Keyframe { time, ..*last });
+        }
+        let pose = match self.interpolation {
+            Interpolation::CatmullRom => {
+                let i = keys.iter().rposition(|k| k.time <= time).unwrap_or(0);
+                let (k1, k2) = (&keys[i], &keys[i + 1]);
+                let k0 = &keys[i.saturating_sub(1)];
+                let k3 = &keys[(i + 2).min(keys.len() - 1)];
+                let t = (time - k1.time) / (k2.time - k1.time);
+                let p = |f: fn(&Keyframe) -> Vec3| catmull_rom(f(k0), f(k1), f(k2), f(k3), t);
+                Keyframe {
+                    time,
+                    position: p(|k| k.position),
+                    target: p(|k| k.target),
+                    fov_y: catmull_rom(k0.fov_y, k1.fov_y, k2.fov_y, k3.fov_y, t),
+                }
+            }
+            Interpolation::Bezier => {
+                let t = (time - first.time) / (last.time - first.time);
+                Keyframe {
+                    time,
+                    position: bezier(keys.iter().map(|k| k.position).collect(), t),
+                    target: bezier(keys.iter().map(|k| k.target).collect(), t),
+                    fov_y: bezier(keys.iter().map(|k| k.fov_y).collect(), t),
+                }
+            }
+        };
+        Some(pose)
+    }
+
+    /// Debug lines showing the camera track, a cross at every keyframe and
+    /// where each keyframe looks.
+    #[cfg(target_arch = "wasm32")]
+    pub fn vertices(&self) -> Vec<Vertex> {
+        let mut verts = Vec::new();
+        if !self.visible || self.keyframes.is_empty() {
+            return verts;
+        }
+        let normal = [0.0, 1.0, 0.0];
+        let mut line = |a: Vec3, b: Vec3, color: [f32; 3]| {
+            verts.push(Vertex { position: a.into(), color, normal });
+            verts.push(Vertex { position: b.into(), color, normal });
+        };
+        for k in &self.keyframes {
+            for axis in [Vec3::X, Vec3::Y, Vec3::Z] {
+                line(k.position - axis * MARKER_SIZE, k.position + axis * MARKER_SIZE, PATH_COLOR);
+            }
+            line(k.position, k.target, TARGET_COLOR);
+        }
+        let (start, end) = (self.start(), self.duration());
+        let steps = (self.keyframes.len() - 1) * SEGMENTS_PER_KEY;
+        let point = |i: usize| {
+            let time = start + (end - start) * i as f32 / steps as f32;
+            self.sample(time).map_or(Vec3::ZERO, |k| k.position)
+        };
+        for i in 0..steps {
+            line(point(i), point(i + 1), PATH_COLOR);
+        }
+        verts
+    }
+
+    fn start(&self) -> f32 {
+        self.keyframes.first().map_or(0.0, |k| k.time)
+    }
+}
+
+/// Uniform Catmull-Rom spline through `p1` and `p2`.
+fn catmull_rom<T>(p0: T, p1: T, p2: T, p3: T, t: f32) -> T
+where
+    T: Copy + Add<Output = T> + Sub<Output = T> + Mul<f32, Output = T>,
+{
+    let (t2, t3) = (t * t, t * t * t);
+    (p1 * 2.0 + (p2 - p0) * t + (p0 * 2.0 - p1 * 5.0 + p2 * 4.0 - p3) * t2
+        + (p1 * 3.0 - p0 - p2 * 3.0 + p3) * t3)
+        * 0.5
+}
+
+/// Bezier curve over all `points`, evaluated with de Casteljau's algorithm.
+fn bezier<T>(mut points: Vec<T>, t: f32) -> T
+where
+    T: Copy + Add<Output = T> + Sub<Output = T> + Mul<f32, Output = T>,
+{
+    for n in (1..points.len()).rev() {
+        for i in 0..n {
+            points[i] = points[i] + (points[i + 1] - points[i]) * t;
+        }
+    }
+    points[0]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(time: f32, x: f32) -> Keyframe {
+        Keyframe { time, position: Vec3::new(x, 1.0, 0.0), target: Vec3::ZERO, fov_y: 1.0 }
+    }
+
+    fn path(interpolation: Interpolation) -> CameraPath {
+        let mut path = CameraPath::new();
+        path.interpolation = interpolation;
+        path.set_keyframes(vec![key(2.0, 4.0), key(0.0, 0.0), key(1.0, 2.0)]);
+        path
+    }
+
+    #[test]
+    fn sample_clamps_to_the_keyframe_range() {
+        let path = path(Interpolation::CatmullRom);
+        assert_eq!(path.sample(-1.0).unwrap().position.x, 0.0);
+        assert_eq!(path.sample(5.0).unwrap().position.x, 4.0);
+        assert!(CameraPath::new().sample(0.0).is_none());
+    }
+
+    #[test]
+    fn catmull_rom_passes_through_keyframes() {
+        let path = path(Interpolation::CatmullRom);
+        assert!((path.sample(1.0).unwrap().position.x - 2.0).abs() < 1e-5);
+        let mut path = path;
+        path.add(key(3.0, 6.0));
+        // Evenly spaced keys on a line stay on it between inner keys.
+        assert!((path.sample(1.5).unwrap().position.x - 3.0).abs() < 1e-5);
+    }
+
+    #[test]
+    fn bezier_only_passes_through_the_ends() {
+        let mut path = path(Interpolation::Bezier);
+        path.set_keyframes(vec![key(0.0, 0.0), key(1.0, 4.0), key(2.0, 0.0)]);
+        let middle = path.sample(1.0).unwrap().position.x;
+        assert!(middle > 0.0 && middle < 4.0);
+        assert_eq!(path.sample(2.0).unwrap().position.x, 0.0);
+    }
+
+    #[test]
+    fn looping_playback_wraps_around() {
+        let mut path = path(Interpolation::CatmullRom);
+        path.looping = true;
+        path.play();
+        path.update(2.5);
+        assert!(path.is_playing());
+        assert!((path.time() - 0.5).abs() < 1e-5);
+    }
+
+    #[test]
+    fn validate_rejects_unusable_keyframes() {
+        assert!(key(0.0, 1.0).validate().is_ok());
+        assert!(Keyframe { fov_y: 0.0, ..key(0.0, 1.0) }.validate().is_err());
+        assert!(Keyframe { time: f32::NAN, ..key(0.0, 1.0) }.validate().is_err());
+        assert!(Keyframe { target: Vec3::new(1.0, 1.0, 0.0), ..key(0.0, 1.0) }.validate().is_err());
+    }
+}
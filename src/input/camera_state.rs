@@ -0,0 +1,232 @@
+use glam::{Quat, Vec3};
+use serde::{Deserialize, Serialize};
+#[cfg(target_arch = "wasm32")]
+use std::cell::RefCell;
+#[cfg(target_arch = "wasm32")]
+use std::rc::Rc;
+
+#[cfg(target_arch = "wasm32")]
+use crate::input::listener::Listener;
+use crate::input::projection::Lens;
+#[cfg(target_arch = "wasm32")]
+use crate::input::viewports::Viewports;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CameraType {
+    Free,
+    Orbit,
+    Arcball,
+    Walk,
+}
+
+/// Pose of the free camera.
+#[derive(Copy, Clone, Debug, Serialize, Deserialize)]
+pub struct FreeState {
+    pub position: Vec3,
+    pub yaw: f32,
+    pub pitch: f32,
+    pub focus_distance: f32,
+    pub lens: Lens,
+}
+
+/// Pose of the orbit camera.
+#[derive(Copy, Clone, Debug, Serialize, Deserialize)]
+pub struct OrbitState {
+    pub target: Vec3,
+    pub radius: f32,
+    pub yaw: f32,
+    pub pitch: f32,
+    pub lens: Lens,
+}
+
+/// Pose of the arcball camera.
+#[derive(Copy, Clone, Debug, Serialize, Deserialize)]
+pub struct ArcballState {
+    pub target: Vec3,
+    pub radius: f32,
+    pub rotation: Quat,
+    pub lens: Lens,
+}
+
+/// Stands in for states saved before the arcball camera existed.
+impl Default for ArcballState {
+    fn default() -> Self {
+        Self {
+            target: Vec3::ZERO,
+            radius: 3.0,
+            rotation: Quat::IDENTITY,
+            lens: Lens::default(),
+        }
+    }
+}
+
+/// Pose of the walk camera.
+#[derive(Copy, Clone, Debug, Default, Serialize, Deserialize)]
+pub struct WalkState {
+    /// Point on the floor below the eye.
+    pub feet: Vec3,
+    pub yaw: f32,
+    pub pitch: f32,
+    pub lens: Lens,
+}
+
+/// Everything needed to reproduce a viewpoint: which camera is active and
+/// the pose and projection of each camera.
+#[derive(Copy, Clone, Debug, Serialize, Deserialize)]
+pub struct CameraState {
+    #[serde(rename = "type")]
+    pub camera: CameraType,
+    pub free: FreeState,
+    pub orbit: OrbitState,
+    #[serde(default)]
+    pub arcball: ArcballState,
+    #[serde(default)]
+    pub walk: WalkState,
+}
+
+impl CameraState {
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).unwrap_or_default()
+    }
+
+    /// Parses and validates a state written by `to_json`.
+    pub fn from_json(json: &str) -> Result<Self, String> {
+        let state: Self = serde_json::from_str(json).map_err(|e| e.to_string())?;
+        state.validate()?;
+        Ok(state)
+    }
+
+    /// Rejects states that would leave the viewer blank: non-finite poses,
+    /// distances that are not positive (a zero-size orthographic view) and
+    /// lenses without a usable projection. States can come from shared URLs,
+    /// so they are not trusted.
+    pub fn validate(&self) -> Result<(), String> {
+        let finite = |values: &[f32]| values.iter().all(|v| v.is_finite());
+        let Self { free, orbit, arcball, walk, .. } = self;
+        let poses_ok = free.position.is_finite()
+            && finite(&[free.yaw, free.pitch, free.focus_distance])
+            && orbit.target.is_finite()
+            && finite(&[orbit.radius, orbit.yaw, orbit.pitch])
+            && arcball.target.is_finite()
+            && arcball.radius.is_finite()
+            && arcball.rotation.is_finite()
+            && arcball.rotation.length_squared() > 0.0
+            && walk.feet.is_finite()
+            && finite(&[walk.yaw, walk.pitch]);
+        if !poses_ok {
+            return Err("camera pose is not finite".into());
+        }
+        if !(free.focus_distance > 0.0 && orbit.radius > 0.0 && arcball.radius > 0.0) {
+            return Err("camera distance must be positive".into());
+        }
+        if ![free.lens, orbit.lens, arcball.lens, walk.lens].iter().all(Lens::is_valid) {
+            return Err("camera lens has no valid projection".into());
+        }
+        Ok(())
+    }
+
+    /// Encodes the state for the URL hash, e.g. `#view=%7B...%7D`.
+    #[cfg(target_arch = "wasm32")]
+    pub fn to_hash(&self) -> String {
+        format!("#view={}", js_sys::encode_uri_component(&self.to_json()))
+    }
+
+    /// Parses a URL hash written by `to_hash`. Returns `None` for hashes
+    /// that do not carry a view.
+    #[cfg(target_arch = "wasm32")]
+    pub fn from_hash(hash: &str) -> Option<Self> {
+        let encoded = hash.strip_prefix('#').unwrap_or(hash).strip_prefix("view=")?;
+        let json = js_sys::decode_uri_component(encoded).ok()?.as_string()?;
+        Self::from_json(&json).ok()
+    }
+}
+
+/// Restores the main view from the URL hash, now and whenever the hash
+/// changes. A hash the viewer wrote itself into `written` is not restored
+/// again.
+#[cfg(target_arch = "wasm32")]
+pub fn attach(
+    window: &web_sys::Window,
+    cam: Rc<RefCell<Viewports>>,
+    written: Rc<RefCell<Option<String>>>,
+) -> Vec<Listener> {
+    let location = window.location();
+    if let Some(state) = location.hash().ok().and_then(|h| CameraState::from_hash(&h)) {
+        cam.borrow_mut().main_mut().set_state(&state, false);
+    }
+
+    vec![Listener::new(window, "hashchange", move |_: web_sys::Event| {
+        let hash = location.hash().unwrap_or_default();
+        if written.borrow_mut().take().as_deref() == Some(hash.as_str()) {
+            return;
+        }
+        if let Some(state) = CameraState::from_hash(&hash) {
+            cam.borrow_mut().main_mut().set_state(&state, true);
+        }
+    })]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn state() -> CameraState {
+        CameraState {
+            camera: CameraType::Orbit,
+            free: FreeState {
+                position: Vec3::new(0.0, 1.0, -3.0),
+                yaw: 0.0,
+                pitch: 0.0,
+                focus_distance: 3.0,
+                lens: Lens::default(),
+            },
+            orbit: OrbitState { target: Vec3::ZERO, radius: 3.0, yaw: 0.5, pitch: 0.3, lens: Lens::default() },
+            arcball: ArcballState::default(),
+            walk: WalkState { lens: Lens::default(), ..WalkState::default() },
+        }
+    }
+
+    #[test]
+    fn json_round_trips() {
+        let restored = CameraState::from_json(&state().to_json()).unwrap();
+        assert_eq!(restored.camera, CameraType::Orbit);
+        assert_eq!(restored.orbit.radius, 3.0);
+    }
+
+    #[test]
+    fn states_without_arcball_or_walk_stay_valid() {
+        let mut json: serde_json::Value = serde_json::from_str(&state().to_json()).unwrap();
+        let fields = json.as_object_mut().unwrap();
+        fields.remove("arcball");
+        fields.remove("walk");
+        assert!(CameraState::from_json(&json.to_string()).is_ok());
+    }
+
+    #[test]
+    fn validate_rejects_non_finite_poses() {
+        let mut s = state();
+        s.free.position.x = f32::NAN;
+        assert!(s.validate().is_err());
+        let mut s = state();
+        s.arcball.rotation = Quat::from_xyzw(0.0, 0.0, 0.0, 0.0);
+        assert!(s.validate().is_err());
+    }
+
+    #[test]
+    fn validate_rejects_non_positive_distances() {
+        let mut s = state();
+        s.orbit.radius = 0.0;
+        assert!(s.validate().is_err());
+        let mut s = state();
+        s.free.focus_distance = -1.0;
+        assert!(s.validate().is_err());
+    }
+
+    #[test]
+    fn validate_rejects_singular_lenses() {
+        let mut s = state();
+        s.walk.lens.far = s.walk.lens.near;
+        assert!(s.validate().is_err());
+    }
+}
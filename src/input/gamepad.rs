@@ -0,0 +1,92 @@
+#![cfg(target_arch = "wasm32")]
+
+use glam::Vec2;
+use wasm_bindgen::JsCast;
+use web_sys::{Gamepad, Window};
+
+/// Analog camera input for one frame. Each axis is in `-1..=1`.
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct AnalogInput {
+    /// `x` strafes right, `y` moves forward.
+    pub movement: Vec2,
+    /// `x` turns right, `y` looks up.
+    pub look: Vec2,
+    /// Positive zooms in.
+    pub zoom: f32,
+}
+
+impl AnalogInput {
+    pub fn is_idle(&self) -> bool {
+        self.movement == Vec2::ZERO && self.look == Vec2::ZERO && self.zoom == 0.0
+    }
+}
+
+#[derive(Copy, Clone, Debug)]
+pub struct GamepadSettings {
+    /// Stick deflection below which input is ignored.
+    pub deadzone: f32,
+    pub invert_x: bool,
+    pub invert_y: bool,
+}
+
+impl Default for GamepadSettings {
+    fn default() -> Self {
+        Self {
+            deadzone: 0.15,
+            invert_x: false,
+            invert_y: false,
+        }
+    }
+}
+
+// Indices of the W3C "standard" gamepad mapping.
+const LEFT_X: u32 = 0;
+const LEFT_Y: u32 = 1;
+const RIGHT_X: u32 = 2;
+const RIGHT_Y: u32 = 3;
+const LEFT_TRIGGER: u32 = 6;
+const RIGHT_TRIGGER: u32 = 7;
+
+/// Radial deadzone, rescaled so output starts at zero at the deadzone edge.
+fn apply_deadzone(v: Vec2, deadzone: f32) -> Vec2 {
+    let len = v.length();
+    if len <= deadzone {
+        return Vec2::ZERO;
+    }
+    let scaled = ((len - deadzone) / (1.0 - deadzone)).min(1.0);
+    v / len * scaled
+}
+
+fn axis(pad: &Gamepad, index: u32) -> f32 {
+    pad.axes().get(index).as_f64().unwrap_or(0.0) as f32
+}
+
+fn button(pad: &Gamepad, index: u32) -> f32 {
+    pad.buttons()
+        .get(index)
+        .dyn_into::<web_sys::GamepadButton>()
+        .map_or(0.0, |b| b.value() as f32)
+}
+
+/// Reads the first connected gamepad. Call once per frame; the Gamepad API
+/// has no events for axis changes.
+pub fn poll(window: &Window, settings: &GamepadSettings) -> Option<AnalogInput> {
+    let pads = window.navigator().get_gamepads().ok()?;
+    let pad = pads
+        .iter()
+        .filter_map(|p| p.dyn_into::<Gamepad>().ok())
+        .find(|p| p.connected())?;
+    let stick = |x, y| apply_deadzone(Vec2::new(axis(&pad, x), -axis(&pad, y)), settings.deadzone);
+    let mut look = stick(RIGHT_X, RIGHT_Y);
+    if settings.invert_x {
+        look.x = -look.x;
+    }
+    if settings.invert_y {
+        look.y = -look.y;
+    }
+    Some(AnalogInput {
+        movement: stick(LEFT_X, LEFT_Y),
+        look,
+        zoom: button(&pad, RIGHT_TRIGGER) - button(&pad, LEFT_TRIGGER),
+    })
+}
@@ -0,0 +1,138 @@
+use glam::Vec2;
+
+/// Camera gesture recognised from touch pointers.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Gesture {
+    /// One-finger drag by the given delta in pixels.
+    Orbit(Vec2),
+    /// Two-finger pinch. `scale` is the ratio of the new to the previous
+    /// finger distance (> 1 when spreading), `center` the midpoint in pixels.
+    Pinch { scale: f32, center: Vec2 },
+    /// Two-finger drag of the midpoint by the given delta in pixels.
+    Pan(Vec2),
+}
+
+/// Turns pointer down/move/up sequences, keyed by `pointerId`, into
+/// gestures. It has no browser dependencies so it can be driven by
+/// synthetic pointer sequences.
+#[derive(Default)]
+pub struct GestureRecognizer {
+    /// Active pointers in the order they went down.
+    pointers: Vec<(i32, Vec2)>,
+}
+
+impl GestureRecognizer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn active(&self) -> usize {
+        self.pointers.len()
+    }
+
+    pub fn pointer_down(&mut self, id: i32, pos: Vec2) {
+        match self.pointers.iter_mut().find(|(p, _)| *p == id) {
+            Some(entry) => entry.1 = pos,
+            None => self.pointers.push((id, pos)),
+        }
+    }
+
+    /// Also use for `pointercancel`.
+    pub fn pointer_up(&mut self, id: i32) {
+        self.pointers.retain(|(p, _)| *p != id);
+    }
+
+    /// Records the new position of pointer `id` and returns the gestures the
+    /// move produced. Only the first two fingers take part in a gesture.
+    pub fn pointer_move(&mut self, id: i32, pos: Vec2) -> Vec<Gesture> {
+        let Some(index) = self.pointers.iter().position(|(p, _)| *p == id) else {
+            return Vec::new();
+        };
+        let before = self.pointers.clone();
+        self.pointers[index].1 = pos;
+        match (self.pointers.len(), index) {
+            (1, _) => vec![Gesture::Orbit(pos - before[0].1)],
+            (_, 0 | 1) => {
+                let (a0, b0) = (before[0].1, before[1].1);
+                let (a1, b1) = (self.pointers[0].1, self.pointers[1].1);
+                let (mid0, mid1) = ((a0 + b0) * 0.5, (a1 + b1) * 0.5);
+                let mut gestures = Vec::new();
+                let (d0, d1) = (a0.distance(b0), a1.distance(b1));
+                if d0 > f32::EPSILON && d1 != d0 {
+                    gestures.push(Gesture::Pinch {
+                        scale: d1 / d0,
+                        center: mid1,
+                    });
+                }
+                if mid1 != mid0 {
+                    gestures.push(Gesture::Pan(mid1 - mid0));
+                }
+                gestures
+            }
+            _ => Vec::new(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn one_finger_drag_orbits() {
+        let mut g = GestureRecognizer::new();
+        g.pointer_down(1, Vec2::new(10.0, 10.0));
+        assert_eq!(g.pointer_move(1, Vec2::new(15.0, 7.0)), vec![Gesture::Orbit(Vec2::new(5.0, -3.0))]);
+    }
+
+    #[test]
+    fn two_finger_spread_pinches_out() {
+        let mut g = GestureRecognizer::new();
+        g.pointer_down(1, Vec2::new(0.0, 0.0));
+        g.pointer_down(2, Vec2::new(10.0, 0.0));
+        let gestures = g.pointer_move(2, Vec2::new(20.0, 0.0));
+        assert!(matches!(gestures[0], Gesture::Pinch { scale, .. } if scale > 1.0));
+    }
+
+    #[test]
+    fn two_finger_parallel_move_pans() {
+        let mut g = GestureRecognizer::new();
+        g.pointer_down(1, Vec2::new(0.0, 0.0));
+        g.pointer_down(2, Vec2::new(10.0, 0.0));
+        // Browsers report each finger separately, so a parallel drag
+        // arrives as two moves whose pinches cancel out.
+        let mut gestures = g.pointer_move(1, Vec2::new(0.0, 4.0));
+        gestures.extend(g.pointer_move(2, Vec2::new(10.0, 4.0)));
+        let mut pan = Vec2::ZERO;
+        let mut scale = 1.0;
+        for gesture in gestures {
+            match gesture {
+                Gesture::Pan(d) => pan += d,
+                Gesture::Pinch { scale: s, .. } => scale *= s,
+                Gesture::Orbit(_) => panic!("two fingers must not orbit"),
+            }
+        }
+        assert!(pan.abs_diff_eq(Vec2::new(0.0, 4.0), 1e-5));
+        assert!((scale - 1.0).abs() < 1e-5);
+    }
+
+    #[test]
+    fn lifting_a_finger_mid_pinch_falls_back_to_orbit() {
+        let mut g = GestureRecognizer::new();
+        g.pointer_down(1, Vec2::new(0.0, 0.0));
+        g.pointer_down(2, Vec2::new(10.0, 0.0));
+        g.pointer_move(2, Vec2::new(20.0, 0.0));
+        g.pointer_up(1);
+        assert_eq!(g.active(), 1);
+        assert_eq!(g.pointer_move(2, Vec2::new(22.0, 0.0)), vec![Gesture::Orbit(Vec2::new(2.0, 0.0))]);
+    }
+
+    #[test]
+    fn unknown_pointer_is_ignored() {
+        let mut g = GestureRecognizer::new();
+        g.pointer_down(1, Vec2::ZERO);
+        assert!(g.pointer_move(7, Vec2::new(5.0, 5.0)).is_empty());
+        g.pointer_up(7);
+        assert_eq!(g.active(), 1);
+    }
+}
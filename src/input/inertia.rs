@@ -0,0 +1,137 @@
+use glam::Vec3;
+use std::collections::HashSet;
+use std::ops::{Add, Mul, Sub};
+
+use crate::input::bindings::Action;
+
+/// Closest the orbiting cameras get to their target.
+pub const MIN_RADIUS: f32 = 0.5;
+/// Radius change per wheel step.
+pub const ZOOM_STEP: f32 = 0.1;
+/// Smoothing rate of the drag speed measured for momentum.
+pub const DRAG_SMOOTHING: f32 = 30.0;
+/// Momentum below this many pixels per second stops.
+pub const MIN_MOMENTUM: f32 = 1.0;
+/// Duration of the animated snap to a standard view, in seconds.
+pub const SNAP_DURATION: f32 = 0.4;
+
+/// Tunable response of camera motion. Rates are per second; a non-positive
+/// rate disables the corresponding smoothing.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Inertia {
+    /// How quickly held movement keys bring the camera up to speed.
+    pub acceleration: f32,
+    /// How quickly movement slows down once keys are released.
+    pub damping: f32,
+    /// How quickly orbit momentum fades after releasing a drag.
+    pub rotation_damping: f32,
+}
+
+impl Default for Inertia {
+    fn default() -> Self {
+        Self {
+            acceleration: 10.0,
+            damping: 8.0,
+            rotation_damping: 5.0,
+        }
+    }
+}
+
+impl Inertia {
+    /// Rate at which a velocity follows what the keys ask for: speeding up
+    /// while they ask for motion, slowing down once they stop.
+    pub fn rate(&self, moving: bool) -> f32 {
+        if moving {
+            self.acceleration
+        } else {
+            self.damping
+        }
+    }
+}
+
+/// Velocity the held movement keys ask for, moving at `speed` along the
+/// camera's `forward` and `right` directions. Diagonals add up.
+pub fn wish_velocity(pressed: &HashSet<Action>, forward: Vec3, right: Vec3, speed: f32) -> Vec3 {
+    let mut wish = Vec3::ZERO;
+    if pressed.contains(&Action::MoveForward) {
+        wish += forward;
+    }
+    if pressed.contains(&Action::MoveBackward) {
+        wish -= forward;
+    }
+    if pressed.contains(&Action::MoveLeft) {
+        wish -= right;
+    }
+    if pressed.contains(&Action::MoveRight) {
+        wish += right;
+    }
+    wish * speed
+}
+
+/// Radius change per second the held zoom keys ask for.
+pub fn wish_zoom(pressed: &HashSet<Action>, speed: f32) -> f32 {
+    let mut wish = 0.0;
+    if pressed.contains(&Action::ZoomIn) {
+        wish -= 1.0;
+    }
+    if pressed.contains(&Action::ZoomOut) {
+        wish += 1.0;
+    }
+    wish * speed
+}
+
+/// Speed below which smoothed motion counts as settled, so on-demand
+/// rendering can stop.
+pub const REST_SPEED: f32 = 1e-3;
+
+/// Moves `current` towards `target` by exponential smoothing. The result
+/// only depends on the elapsed time, not on how it is split into frames.
+pub fn approach<T>(current: T, target: T, rate: f32, dt: f32) -> T
+where
+    T: Copy + Add<Output = T> + Sub<Output = T> + Mul<f32, Output = T>,
+{
+    if rate <= 0.0 {
+        return target;
+    }
+    current + (target - current) * (1.0 - (-rate * dt).exp())
+}
+
+/// Factor by which a velocity decaying at `rate` shrinks over `dt`.
+pub fn decay(rate: f32, dt: f32) -> f32 {
+    (-rate * dt).exp()
+}
+
+/// Distance covered over `dt` by a unit velocity decaying at `rate`.
+pub fn coast(rate: f32, dt: f32) -> f32 {
+    (1.0 - decay(rate, dt)) / rate
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn approach_does_not_depend_on_frame_rate() {
+        let once = approach(0.0, 1.0, 5.0, 0.2);
+        let mut split = 0.0;
+        for _ in 0..4 {
+            split = approach(split, 1.0, 5.0, 0.05);
+        }
+        assert!((once - split).abs() < 1e-5);
+        assert!(once > 0.0 && once < 1.0);
+    }
+
+    #[test]
+    fn approach_without_smoothing_snaps() {
+        assert_eq!(approach(Vec3::ZERO, Vec3::ONE, 0.0, 0.01), Vec3::ONE);
+    }
+
+    #[test]
+    fn coast_matches_the_decayed_velocity() {
+        // Integrating the decayed velocity gives the coasted distance.
+        let (rate, dt, steps) = (4.0, 0.5, 10_000);
+        let h = dt / steps as f32;
+        let distance: f32 = (0..steps).map(|i| decay(rate, (i as f32 + 0.5) * h) * h).sum();
+        assert!((distance - coast(rate, dt)).abs() < 1e-4);
+    }
+}
@@ -1,30 +1,79 @@
 #![cfg(target_arch = "wasm32")]
 
-use std::{cell::RefCell, rc::Rc};
-use wasm_bindgen::{closure::Closure, JsCast};
-use web_sys::Window;
+use std::{cell::{Cell, RefCell}, rc::{Rc, Weak}};
+use wasm_bindgen::JsCast;
+use web_sys::{HtmlCanvasElement, PointerEvent, Window};
 
+use crate::host::Host;
+use crate::input::bindings::InputMap;
 use crate::input::camera::CameraController;
+use crate::input::listener::Listener;
 
-pub fn attach<T>(window: &Window, cam: Rc<RefCell<T>>)
+thread_local! {
+    /// Canvases of the viewers on the page with their focus flags. A flag
+    /// is gone once its viewer is disposed.
+    static VIEWERS: RefCell<Vec<(HtmlCanvasElement, Weak<Cell<bool>>)>> = const { RefCell::new(Vec::new()) };
+}
+
+/// Tracks whether `canvas` has keyboard focus: it gains it when clicked and
+/// loses it when another viewer's canvas is, so page controls such as
+/// buttons and unrelated canvases keep it. The first viewer on the page
+/// starts focused so a lone viewer takes keys right away.
+pub fn focus(window: &Window, canvas: &HtmlCanvasElement) -> (Rc<Cell<bool>>, Listener) {
+    let focused = VIEWERS.with(|viewers| {
+        let mut viewers = viewers.borrow_mut();
+        viewers.retain(|(_, f)| f.strong_count() > 0);
+        let focused = Rc::new(Cell::new(viewers.is_empty()));
+        viewers.push((canvas.clone(), Rc::downgrade(&focused)));
+        focused
+    });
+    let focused_c = focused.clone();
+    let canvas = canvas.clone();
+    let listener = Listener::new(window, "pointerdown", move |e: PointerEvent| {
+        let Some(other) = e.target().and_then(|t| t.dyn_into::<HtmlCanvasElement>().ok()) else {
+            return;
+        };
+        let is_viewer = VIEWERS.with(|viewers| {
+            viewers
+                .borrow()
+                .iter()
+                .any(|(c, f)| *c == other && f.strong_count() > 0)
+        });
+        if is_viewer {
+            focused_c.set(other == canvas);
+        }
+    });
+    (focused, listener)
+}
+
+/// Translates key events to camera actions through `map` while `focused`.
+/// Releases always go through so no key stays held after focus moves.
+pub fn attach<T>(
+    host: &Host,
+    focused: Rc<Cell<bool>>,
+    cam: Rc<RefCell<T>>,
+    map: Rc<RefCell<InputMap>>,
+) -> Vec<Listener>
 where
     T: CameraController + 'static,
 {
+    let mut listeners = Vec::new();
     let cam_down = cam.clone();
-    let key_down = Closure::wrap(Box::new(move |e: web_sys::KeyboardEvent| {
-        cam_down.borrow_mut().key_down(e.code());
-    }) as Box<dyn FnMut(_)>);
-    window
-        .add_event_listener_with_callback("keydown", key_down.as_ref().unchecked_ref())
-        .unwrap();
-    key_down.forget();
+    let map_down = map.clone();
+    listeners.push(Listener::new(host.window_events(), "keydown", move |e: web_sys::KeyboardEvent| {
+        if !focused.get() {
+            return;
+        }
+        if let Some(action) = map_down.borrow().action(&e.code()) {
+            cam_down.borrow_mut().action_down(action);
+        }
+    }));
 
     let cam_up = cam.clone();
-    let key_up = Closure::wrap(Box::new(move |e: web_sys::KeyboardEvent| {
-        cam_up.borrow_mut().key_up(e.code());
-    }) as Box<dyn FnMut(_)>);
-    window
-        .add_event_listener_with_callback("keyup", key_up.as_ref().unchecked_ref())
-        .unwrap();
-    key_up.forget();
+    listeners.push(Listener::new(host.window_events(), "keyup", move |e: web_sys::KeyboardEvent| {
+        if let Some(action) = map.borrow().action(&e.code()) {
+            cam_up.borrow_mut().action_up(action);
+        }
+    }));
+    listeners
 }
@@ -0,0 +1,38 @@
+#![cfg(target_arch = "wasm32")]
+
+use wasm_bindgen::{closure::Closure, JsCast};
+use web_sys::{Event, EventTarget};
+
+/// An event listener that is removed from its target when dropped, so a
+/// viewer can be torn down without leaking its handlers.
+pub struct Listener {
+    target: EventTarget,
+    event: &'static str,
+    callback: Closure<dyn FnMut(Event)>,
+}
+
+impl Listener {
+    /// Calls `f` for every `event` on `target`, with the event cast to `E`.
+    pub fn new<E>(target: &EventTarget, event: &'static str, mut f: impl FnMut(E) + 'static) -> Self
+    where
+        E: JsCast + 'static,
+    {
+        let callback = Closure::wrap(Box::new(move |e: Event| f(e.unchecked_into())) as Box<dyn FnMut(_)>);
+        target
+            .add_event_listener_with_callback(event, callback.as_ref().unchecked_ref())
+            .unwrap();
+        Self {
+            target: target.clone(),
+            event,
+            callback,
+        }
+    }
+}
+
+impl Drop for Listener {
+    fn drop(&mut self) {
+        let _ = self
+            .target
+            .remove_event_listener_with_callback(self.event, self.callback.as_ref().unchecked_ref());
+    }
+}
@@ -1,7 +1,34 @@
-#![cfg(target_arch = "wasm32")]
-
+#[cfg(target_arch = "wasm32")]
+pub mod arcball_camera;
+pub mod bindings;
+#[cfg(target_arch = "wasm32")]
 pub mod camera;
+pub mod camera_path;
+pub mod camera_state;
+#[cfg(target_arch = "wasm32")]
+pub mod gamepad;
+pub mod gesture;
+pub mod inertia;
+#[cfg(target_arch = "wasm32")]
 pub mod orbit_camera;
+#[cfg(target_arch = "wasm32")]
 pub mod active_camera;
+#[cfg(target_arch = "wasm32")]
 pub mod keyboard;
+#[cfg(target_arch = "wasm32")]
+pub mod listener;
+#[cfg(target_arch = "wasm32")]
 pub mod mouse;
+#[cfg(target_arch = "wasm32")]
+pub mod picking;
+#[cfg(target_arch = "wasm32")]
+pub mod pointer_lock;
+pub mod projection;
+#[cfg(target_arch = "wasm32")]
+pub mod transform_gizmo;
+#[cfg(target_arch = "wasm32")]
+pub mod view_cube;
+#[cfg(target_arch = "wasm32")]
+pub mod viewports;
+#[cfg(target_arch = "wasm32")]
+pub mod walk_camera;
@@ -1,66 +1,180 @@
 #![cfg(target_arch = "wasm32")]
 
 use std::{cell::RefCell, rc::Rc};
-use wasm_bindgen::{closure::Closure, JsCast};
-use web_sys::{Window, HtmlCanvasElement, PointerEvent};
+use glam::Vec2;
+use web_sys::{MouseEvent, PointerEvent, WheelEvent};
 
+use crate::host::Host;
 use crate::input::camera::CameraController;
+use crate::input::gesture::{Gesture, GestureRecognizer};
+use crate::input::listener::Listener;
 
-pub fn attach<T>(window: &Window, canvas: &HtmlCanvasElement, cam: Rc<RefCell<T>>)
+/// Wheel steps per unit of log pinch scale; spreading two fingers to double
+/// their distance zooms like about seven wheel notches.
+const PINCH_STEPS: f32 = 10.0;
+
+/// Pointer position in normalized device coordinates of the canvas (y up),
+/// for canvas and window events alike.
+pub fn ndc(host: &Host, e: &MouseEvent) -> Vec2 {
+    let Vec2 { x: w, y: h } = host.css_size().max(Vec2::ONE);
+    let Vec2 { x, y } = host.pointer_position(e);
+    Vec2::new(x / w * 2.0 - 1.0, 1.0 - y / h * 2.0)
+}
+
+#[derive(Copy, Clone, PartialEq)]
+enum Drag {
+    Rotate,
+    Pan,
+}
+
+impl Drag {
+    /// Bits of `PointerEvent::buttons` that keep this drag alive.
+    fn buttons(self) -> u16 {
+        match self {
+            Drag::Rotate => 1,
+            Drag::Pan => 2 | 4,
+        }
+    }
+}
+
+fn is_touch(e: &PointerEvent) -> bool {
+    e.pointer_type() == "touch"
+}
+
+/// Position relative to the canvas. Touch pointers are implicitly captured by
+/// the element they went down on, so their offsets stay canvas-relative.
+fn touch_pos(e: &PointerEvent) -> Vec2 {
+    Vec2::new(e.offset_x() as f32, e.offset_y() as f32)
+}
+
+fn apply_gesture<T: CameraController>(cam: &mut T, host: &Host, gesture: Gesture) {
+    let Vec2 { x: w, y: h } = host.css_size().max(Vec2::ONE);
+    match gesture {
+        Gesture::Orbit(d) => cam.mouse_move(d.x, d.y),
+        Gesture::Pan(d) => cam.pan(d.x / h * 2.0, d.y / h * 2.0),
+        Gesture::Pinch { scale, center } => {
+            let ndc = Vec2::new(center.x / w * 2.0 - 1.0, 1.0 - center.y / h * 2.0);
+            cam.zoom(scale.ln() * PINCH_STEPS, ndc);
+        }
+    }
+}
+
+/// Wheel delta in "steps", roughly one per notch of a mouse wheel.
+fn wheel_steps(e: &WheelEvent) -> f32 {
+    let delta = -e.delta_y() as f32;
+    match e.delta_mode() {
+        WheelEvent::DOM_DELTA_PIXEL => delta / 100.0,
+        WheelEvent::DOM_DELTA_LINE => delta / 3.0,
+        _ => delta,
+    }
+}
+
+pub fn attach<T>(host: &Host, cam: Rc<RefCell<T>>) -> Vec<Listener>
 where
     T: CameraController + 'static,
 {
-    let dragging = Rc::new(RefCell::new(false));
+    let mut listeners = Vec::new();
+    let dragging = Rc::new(RefCell::new(None::<Drag>));
+    let gestures = Rc::new(RefCell::new(GestureRecognizer::new()));
 
-    // Start dragging only when the left button is pressed on the canvas
+    // Left button rotates, right and middle buttons pan; only when pressed on the canvas.
+    // Touch pointers are tracked individually for multi-finger gestures.
     {
         let dragging = dragging.clone();
-        let canvas_clone = canvas.clone();
-        let on_down = Closure::wrap(Box::new(move |e: PointerEvent| {
-            if e.buttons() & 1 == 1 {
-                if let Some(target) = e.target() {
-                    if target == canvas_clone
-                        .clone()
-                        .dyn_into::<web_sys::EventTarget>()
-                        .unwrap()
-                    {
-                        *dragging.borrow_mut() = true;
+        let gestures = gestures.clone();
+        let host_c = host.clone();
+        listeners.push(Listener::new(host.canvas_events(), "pointerdown", move |e: PointerEvent| {
+            if is_touch(&e) {
+                gestures.borrow_mut().pointer_down(e.pointer_id(), touch_pos(&e));
+                return;
+            }
+            let drag = match e.button() {
+                0 => Drag::Rotate,
+                1 | 2 => Drag::Pan,
+                _ => return,
+            };
+            if let Some(target) = e.target() {
+                if host_c.is_canvas(&target) {
+                    // Middle button would otherwise start auto-scrolling
+                    if e.button() == 1 {
+                        e.prevent_default();
                     }
+                    *dragging.borrow_mut() = Some(drag);
                 }
             }
-        }) as Box<dyn FnMut(_)>);
-        canvas
-            .add_event_listener_with_callback("pointerdown", on_down.as_ref().unchecked_ref())
-            .unwrap();
-        on_down.forget();
+        }));
     }
 
     // Stop dragging on mouseup anywhere in the window
     {
         let dragging = dragging.clone();
-        let on_up = Closure::wrap(Box::new(move |_e: PointerEvent| {
-            *dragging.borrow_mut() = false;
-        }) as Box<dyn FnMut(_)>);
-        window
-            .add_event_listener_with_callback("pointerup", on_up.as_ref().unchecked_ref())
-            .unwrap();
-        on_up.forget();
+        let gestures = gestures.clone();
+        let cam_up = cam.clone();
+        let on_up = Rc::new(move |e: PointerEvent| {
+            let released = if is_touch(&e) {
+                let mut gestures = gestures.borrow_mut();
+                let was_active = gestures.active() > 0;
+                gestures.pointer_up(e.pointer_id());
+                was_active && gestures.active() == 0
+            } else {
+                dragging.borrow_mut().take() == Some(Drag::Rotate)
+            };
+            if released {
+                cam_up.borrow_mut().drag_end();
+            }
+        });
+        for event in ["pointerup", "pointercancel"] {
+            let on_up = on_up.clone();
+            listeners.push(Listener::new(host.window_events(), event, move |e| on_up(e)));
+        }
     }
 
     // Apply mouse movement only while dragging
     {
         let dragging = dragging.clone();
         let cam_mouse = cam.clone();
-        let mouse_move = Closure::wrap(Box::new(move |e: PointerEvent| {
-            if *dragging.borrow() && e.buttons() & 1 == 1 {
-                cam_mouse
-                    .borrow_mut()
-                    .mouse_move(e.movement_x() as f32, e.movement_y() as f32);
+        let host_c = host.clone();
+        listeners.push(Listener::new(host.window_events(), "pointermove", move |e: PointerEvent| {
+            if is_touch(&e) {
+                let moved = gestures.borrow_mut().pointer_move(e.pointer_id(), touch_pos(&e));
+                let mut cam = cam_mouse.borrow_mut();
+                for gesture in moved {
+                    apply_gesture(&mut *cam, &host_c, gesture);
+                }
+                return;
             }
-        }) as Box<dyn FnMut(_)>);
-        window
-            .add_event_listener_with_callback("pointermove", mouse_move.as_ref().unchecked_ref())
-            .unwrap();
-        mouse_move.forget();
+            let Some(drag) = *dragging.borrow() else {
+                return;
+            };
+            if e.buttons() & drag.buttons() == 0 {
+                return;
+            }
+            let (dx, dy) = (e.movement_x() as f32, e.movement_y() as f32);
+            match drag {
+                Drag::Rotate => cam_mouse.borrow_mut().mouse_move(dx, dy),
+                Drag::Pan => {
+                    let h = host_c.css_size().y.max(1.0);
+                    cam_mouse.borrow_mut().pan(dx / h * 2.0, dy / h * 2.0);
+                }
+            }
+        }));
+    }
+
+    // Wheel dollies towards the point under the cursor
+    {
+        let cam_wheel = cam.clone();
+        let host_c = host.clone();
+        listeners.push(Listener::new(host.canvas_events(), "wheel", move |e: WheelEvent| {
+            e.prevent_default();
+            cam_wheel.borrow_mut().zoom(wheel_steps(&e), ndc(&host_c, &e));
+        }));
+    }
+
+    // Right button drags pan, so keep the context menu out of the way
+    {
+        listeners.push(Listener::new(host.canvas_events(), "contextmenu", move |e: MouseEvent| {
+            e.prevent_default();
+        }));
     }
+    listeners
 }
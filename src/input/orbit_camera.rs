@@ -1,6 +1,45 @@
-use glam::{Mat4, Vec3};
+use glam::{Mat4, Vec2, Vec3};
 use std::collections::HashSet;
 
+use crate::input::bindings::Action;
+use crate::input::camera::LOOK_RATE;
+use crate::input::camera_state::OrbitState;
+use crate::input::gamepad::AnalogInput;
+use crate::input::inertia::{
+    self, Inertia, DRAG_SMOOTHING, MIN_MOMENTUM, MIN_RADIUS, SNAP_DURATION, ZOOM_STEP,
+};
+use crate::input::projection::{Lens, Projection};
+use crate::input::view_cube::StandardView;
+use crate::scene::bounds::Aabb;
+
+/// Extra room left around framed bounds.
+const FRAME_MARGIN: f32 = 1.1;
+
+#[derive(Copy, Clone)]
+struct Orbit {
+    target: Vec3,
+    radius: f32,
+    yaw: f32,
+    pitch: f32,
+}
+
+impl Orbit {
+    fn lerp(&self, to: &Orbit, t: f32) -> Orbit {
+        Orbit {
+            target: self.target.lerp(to.target, t),
+            radius: self.radius + (to.radius - self.radius) * t,
+            yaw: self.yaw + (to.yaw - self.yaw) * t,
+            pitch: self.pitch + (to.pitch - self.pitch) * t,
+        }
+    }
+}
+
+struct Transition {
+    from: Orbit,
+    to: Orbit,
+    elapsed: f32,
+}
+
 pub struct OrbitCamera {
     pub position: Vec3,
     target: Vec3,
@@ -9,8 +48,17 @@ pub struct OrbitCamera {
     pitch: f32,
     pub speed: f32,
     pub sensitivity: f32,
-    pressed: HashSet<String>,
+    pub lens: Lens,
+    pub inertia: Inertia,
+    pressed: HashSet<Action>,
     aspect: f32,
+    transition: Option<Transition>,
+    velocity: Vec3,
+    zoom_velocity: f32,
+    /// Drag speed in pixels per second, kept as momentum after release.
+    angular_velocity: Vec2,
+    drag_delta: Vec2,
+    dragging: bool,
 }
 
 impl OrbitCamera {
@@ -29,8 +77,16 @@ impl OrbitCamera {
             pitch,
             speed: 2.0,
             sensitivity: 0.002,
+            lens: Lens::default(),
+            inertia: Inertia::default(),
             pressed: HashSet::new(),
             aspect,
+            transition: None,
+            velocity: Vec3::ZERO,
+            zoom_velocity: 0.0,
+            angular_velocity: Vec2::ZERO,
+            drag_delta: Vec2::ZERO,
+            dragging: false,
         }
     }
 
@@ -38,77 +94,294 @@ impl OrbitCamera {
         self.aspect = aspect;
     }
 
-    pub fn key_down(&mut self, code: String) {
-        self.pressed.insert(code);
+    /// Orbits around the point `distance` along `forward` from `position`,
+    /// keeping the camera where it is. Drops any motion in progress.
+    pub fn look_from(&mut self, position: Vec3, forward: Vec3, distance: f32) {
+        self.radius = distance.max(MIN_RADIUS);
+        self.target = position + forward * self.radius;
+        self.yaw = (-forward.z).atan2(-forward.x);
+        self.pitch = (-forward.y).clamp(-1.0, 1.0).asin().clamp(-1.54, 1.54);
+        self.stop();
+    }
+
+    pub fn state(&self) -> OrbitState {
+        OrbitState {
+            target: self.target,
+            radius: self.radius,
+            yaw: self.yaw,
+            pitch: self.pitch,
+            lens: self.lens,
+        }
+    }
+
+    pub fn set_state(&mut self, state: &OrbitState) {
+        self.target = state.target;
+        self.radius = state.radius.max(MIN_RADIUS);
+        self.yaw = state.yaw;
+        self.pitch = state.pitch.clamp(-1.54, 1.54);
+        self.lens = Lens {
+            reverse_z: self.lens.reverse_z,
+            ..state.lens
+        };
+        self.stop();
+    }
+
+    /// Recomputes the position and drops any motion in progress.
+    fn stop(&mut self) {
+        self.position = self.orbit_position();
+        self.transition = None;
+        self.velocity = Vec3::ZERO;
+        self.zoom_velocity = 0.0;
+        self.angular_velocity = Vec2::ZERO;
     }
 
-    pub fn key_up(&mut self, code: String) {
-        self.pressed.remove(&code);
+    /// Distance from the camera to the orbit target.
+    pub fn radius(&self) -> f32 {
+        self.radius
+    }
+
+    pub fn action_down(&mut self, action: Action) {
+        self.pressed.insert(action);
+    }
+
+    pub fn action_up(&mut self, action: Action) {
+        self.pressed.remove(&action);
     }
 
     pub fn mouse_move(&mut self, dx: f32, dy: f32) {
+        self.transition = None;
+        self.dragging = true;
+        self.drag_delta += Vec2::new(dx, dy);
+        self.rotate(dx, dy);
+    }
+
+    /// Ends a rotation drag; the camera keeps orbiting with the drag speed
+    /// and slows down according to `inertia.rotation_damping`.
+    pub fn drag_end(&mut self) {
+        self.dragging = false;
+        if self.inertia.rotation_damping <= 0.0 {
+            self.angular_velocity = Vec2::ZERO;
+        }
+    }
+
+    fn rotate(&mut self, dx: f32, dy: f32) {
         self.yaw -= dx * self.sensitivity;
         self.pitch = (self.pitch + dy * self.sensitivity).clamp(-1.54, 1.54);
     }
 
-    pub fn update(&mut self, dt: f32) {
-        // Directions relative to the current camera orientation
-        let forward = (self.target - self.position).normalize();
-        let right = Vec3::Y.cross(forward).normalize();
+    pub fn pan(&mut self, dx: f32, dy: f32) {
+        self.transition = None;
+        let inv = self.view().inverse();
+        // Extent of the view at the target, so the target follows the pointer.
+        let scale = self.radius * (self.lens.fov_y * 0.5).tan();
+        let offset = (inv.y_axis.truncate() * dy - inv.x_axis.truncate() * dx) * scale;
+        self.target += offset;
+        self.position += offset;
+    }
 
-        if self.pressed.contains("KeyW") {
-            self.target += forward * self.speed * dt;
-        }
-        if self.pressed.contains("KeyS") {
-            self.target -= forward * self.speed * dt;
+    pub fn zoom(&mut self, amount: f32, ndc: Vec2) {
+        self.transition = None;
+        let radius = (self.radius * (-amount * ZOOM_STEP).exp()).max(MIN_RADIUS);
+        // Shift the target towards the point under the cursor on the focal
+        // plane so that point stays put on screen while the radius shrinks.
+        let ray = CameraController::ray(self, ndc);
+        let forward = (self.target - self.position).normalize();
+        if let Some(t) = ray.intersect_plane(self.target, forward) {
+            let focus = ray.at(t);
+            let offset = (focus - self.target) * (1.0 - radius / self.radius);
+            self.target += offset;
+            self.position += offset;
         }
-        if self.pressed.contains("KeyA") {
-            self.target -= right * self.speed * dt;
+        self.radius = radius;
+    }
+
+    pub fn analog(&mut self, input: &AnalogInput, dt: f32) {
+        if input.look != Vec2::ZERO {
+            self.transition = None;
         }
-        if self.pressed.contains("KeyD") {
-            self.target += right * self.speed * dt;
+        let forward = (self.target - self.position).normalize();
+        let right = Vec3::Y.cross(forward).normalize();
+        self.target += (forward * input.movement.y + right * input.movement.x) * self.speed * dt;
+        self.yaw -= input.look.x * LOOK_RATE * dt;
+        self.pitch = (self.pitch + input.look.y * LOOK_RATE * dt).clamp(-1.54, 1.54);
+        self.radius = (self.radius - input.zoom * self.speed * dt).max(MIN_RADIUS);
+    }
+
+    /// Starts an animated rotation around the target towards `view`.
+    pub fn snap_to(&mut self, view: StandardView) {
+        let (yaw, pitch) = view.angles();
+        // Take the shorter way around instead of unwinding accumulated yaw.
+        let delta = (yaw - self.yaw + std::f32::consts::PI).rem_euclid(std::f32::consts::TAU)
+            - std::f32::consts::PI;
+        let to = Orbit {
+            yaw: self.yaw + delta,
+            pitch,
+            ..self.orbit()
+        };
+        self.animate_to(to);
+    }
+
+    /// Starts an animated move that centres `bounds` and backs off until its
+    /// bounding sphere fits the view, keeping the current direction.
+    pub fn frame_bounds(&mut self, bounds: &Aabb) {
+        let sphere = (bounds.max - bounds.min).length() * 0.5 * FRAME_MARGIN;
+        // Half-angle tangent of the narrower side of the view.
+        let half = (self.lens.fov_y * 0.5).tan() * self.aspect.min(1.0);
+        let radius = match self.lens.projection {
+            Projection::Perspective => sphere / half.atan().sin(),
+            Projection::Orthographic => sphere / half,
+        };
+        let to = Orbit {
+            target: bounds.center(),
+            radius: radius.max(MIN_RADIUS),
+            ..self.orbit()
+        };
+        self.animate_to(to);
+    }
+
+    fn orbit(&self) -> Orbit {
+        Orbit {
+            target: self.target,
+            radius: self.radius,
+            yaw: self.yaw,
+            pitch: self.pitch,
         }
-        if self.pressed.contains("Equal") || self.pressed.contains("NumpadAdd") {
-            self.radius = (self.radius - self.speed * dt).max(0.5);
+    }
+
+    fn animate_to(&mut self, to: Orbit) {
+        self.angular_velocity = Vec2::ZERO;
+        self.velocity = Vec3::ZERO;
+        self.zoom_velocity = 0.0;
+        self.transition = Some(Transition {
+            from: self.orbit(),
+            to,
+            elapsed: 0.0,
+        });
+    }
+
+    pub fn update(&mut self, dt: f32) {
+        if let Some(tr) = &mut self.transition {
+            tr.elapsed += dt;
+            let t = (tr.elapsed / SNAP_DURATION).min(1.0);
+            let t = t * t * (3.0 - 2.0 * t);
+            let orbit = tr.from.lerp(&tr.to, t);
+            self.target = orbit.target;
+            self.radius = orbit.radius;
+            self.yaw = orbit.yaw;
+            self.pitch = orbit.pitch;
+            if t >= 1.0 {
+                self.transition = None;
+            }
         }
-        if self.pressed.contains("Minus") || self.pressed.contains("NumpadSubtract") {
-            self.radius += self.speed * dt;
+
+        if self.dragging {
+            if dt > 0.0 {
+                self.angular_velocity =
+                    inertia::approach(self.angular_velocity, self.drag_delta / dt, DRAG_SMOOTHING, dt);
+            }
+        } else if self.angular_velocity != Vec2::ZERO {
+            let rate = self.inertia.rotation_damping;
+            let step = self.angular_velocity * inertia::coast(rate, dt);
+            self.rotate(step.x, step.y);
+            self.angular_velocity *= inertia::decay(rate, dt);
+            if self.angular_velocity.length() < MIN_MOMENTUM {
+                self.angular_velocity = Vec2::ZERO;
+            }
         }
-        // recalc position
-        self.position = self.target
+        self.drag_delta = Vec2::ZERO;
+
+        // Directions relative to the current camera orientation
+        let forward = (self.target - self.position).normalize();
+        let right = Vec3::Y.cross(forward).normalize();
+
+        let wish = inertia::wish_velocity(&self.pressed, forward, right, self.speed);
+        let wish_zoom = inertia::wish_zoom(&self.pressed, self.speed);
+        let (rate, zoom_rate) = (self.inertia.rate(wish != Vec3::ZERO), self.inertia.rate(wish_zoom != 0.0));
+        self.velocity = inertia::approach(self.velocity, wish, rate, dt);
+        self.zoom_velocity = inertia::approach(self.zoom_velocity, wish_zoom, zoom_rate, dt);
+        self.target += self.velocity * dt;
+        self.radius = (self.radius + self.zoom_velocity * dt).max(MIN_RADIUS);
+        self.position = self.orbit_position();
+    }
+
+    fn orbit_position(&self) -> Vec3 {
+        self.target
             + Vec3::new(
                 self.radius * self.yaw.cos() * self.pitch.cos(),
                 self.radius * self.pitch.sin(),
                 self.radius * self.yaw.sin() * self.pitch.cos(),
-            );
+            )
     }
 
-pub fn matrix(&self) -> Mat4 {
-        let view = Mat4::look_at_lh(self.position, self.target, Vec3::Y);
-        let proj = Mat4::perspective_lh(std::f32::consts::FRAC_PI_4, self.aspect, 0.1, 100.0);
-        proj * view
+    pub fn view(&self) -> Mat4 {
+        Mat4::look_at_lh(self.position, self.target, Vec3::Y)
+    }
+
+    pub fn matrix(&self) -> Mat4 {
+        // In orthographic mode the radius still acts as zoom via the extent.
+        self.lens.matrix(self.aspect, self.radius) * self.view()
     }
 }
 
 use crate::input::camera::CameraController;
 
 impl CameraController for OrbitCamera {
-    fn key_down(&mut self, code: String) {
-        OrbitCamera::key_down(self, code);
+    fn action_down(&mut self, action: Action) {
+        OrbitCamera::action_down(self, action);
     }
 
-    fn key_up(&mut self, code: String) {
-        OrbitCamera::key_up(self, code);
+    fn action_up(&mut self, action: Action) {
+        OrbitCamera::action_up(self, action);
     }
 
     fn mouse_move(&mut self, dx: f32, dy: f32) {
         OrbitCamera::mouse_move(self, dx, dy);
     }
 
+    fn pan(&mut self, dx: f32, dy: f32) {
+        OrbitCamera::pan(self, dx, dy);
+    }
+
+    fn drag_end(&mut self) {
+        OrbitCamera::drag_end(self);
+    }
+
+    fn analog(&mut self, input: &AnalogInput, dt: f32) {
+        OrbitCamera::analog(self, input, dt);
+    }
+
+    fn zoom(&mut self, amount: f32, ndc: Vec2) {
+        OrbitCamera::zoom(self, amount, ndc);
+    }
+
+    fn is_animating(&self) -> bool {
+        self.transition.is_some()
+            || self.angular_velocity != Vec2::ZERO
+            || !self.pressed.is_empty()
+            || self.velocity.length() > inertia::REST_SPEED
+            || self.zoom_velocity.abs() > inertia::REST_SPEED
+    }
+
+    fn snap_to_view(&mut self, view: StandardView) {
+        self.snap_to(view);
+    }
+
+    fn lens(&self) -> &Lens {
+        &self.lens
+    }
+
+    fn lens_mut(&mut self) -> &mut Lens {
+        &mut self.lens
+    }
+
     fn update(&mut self, dt: f32) {
         OrbitCamera::update(self, dt);
     }
 
+    fn view(&self) -> Mat4 {
+        OrbitCamera::view(self)
+    }
+
     fn matrix(&self) -> Mat4 {
         OrbitCamera::matrix(self)
     }
@@ -0,0 +1,73 @@
+#![cfg(target_arch = "wasm32")]
+
+use std::{cell::RefCell, rc::Rc};
+use glam::Vec2;
+use web_sys::PointerEvent;
+
+use crate::host::Host;
+use crate::input::camera::CameraController;
+use crate::input::listener::Listener;
+use crate::input::mouse;
+use crate::scene::object::Scene;
+
+/// Maximum pointer travel in CSS pixels for a press to count as a click
+/// rather than a camera drag.
+const CLICK_SLOP: f32 = 4.0;
+
+/// Selects the object under the pointer on left click, or clears the
+/// selection when clicking empty space. Also tracks the hovered object.
+pub fn attach<T>(host: &Host, cam: Rc<RefCell<T>>, scene: Rc<RefCell<Scene>>) -> Vec<Listener>
+where
+    T: CameraController + 'static,
+{
+    let mut listeners = Vec::new();
+    let pressed_at = Rc::new(RefCell::new(None::<Vec2>));
+
+    {
+        let pressed_at = pressed_at.clone();
+        listeners.push(Listener::new(host.canvas_events(), "pointerdown", move |e: PointerEvent| {
+            if e.button() == 0 {
+                *pressed_at.borrow_mut() = Some(Vec2::new(e.offset_x() as f32, e.offset_y() as f32));
+            }
+        }));
+    }
+
+    {
+        let cam = cam.clone();
+        let scene = scene.clone();
+        let host_c = host.clone();
+        listeners.push(Listener::new(host.canvas_events(), "pointermove", move |e: PointerEvent| {
+            // Keep the hover state while dragging the camera or a gizmo.
+            if e.buttons() != 0 {
+                return;
+            }
+            let ray = cam.borrow().ray(mouse::ndc(&host_c, &e));
+            let mut scene = scene.borrow_mut();
+            scene.hovered = scene.pick(&ray);
+        }));
+    }
+
+    {
+        let scene = scene.clone();
+        listeners.push(Listener::new(host.canvas_events(), "pointerleave", move |_e: PointerEvent| {
+            scene.borrow_mut().hovered = None;
+        }));
+    }
+
+    {
+        let host_c = host.clone();
+        listeners.push(Listener::new(host.canvas_events(), "pointerup", move |e: PointerEvent| {
+            let Some(start) = pressed_at.borrow_mut().take() else {
+                return;
+            };
+            let pos = Vec2::new(e.offset_x() as f32, e.offset_y() as f32);
+            if e.button() != 0 || pos.distance(start) > CLICK_SLOP {
+                return;
+            }
+            let ray = cam.borrow().ray(mouse::ndc(&host_c, &e));
+            let mut scene = scene.borrow_mut();
+            scene.selected = scene.pick(&ray);
+        }));
+    }
+    listeners
+}
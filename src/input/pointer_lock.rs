@@ -0,0 +1,54 @@
+#![cfg(target_arch = "wasm32")]
+
+use std::{cell::RefCell, rc::Rc};
+use web_sys::{KeyboardEvent, PointerEvent};
+
+use crate::host::Host;
+use crate::input::camera::CameraController;
+use crate::input::listener::Listener;
+
+/// First-person mouse look: clicking the canvas locks the pointer when the
+/// camera asks for it, unbounded movement then turns the camera, and Escape
+/// releases it. Must be attached before `mouse::attach` so locked clicks do
+/// not also start a drag.
+pub fn attach<T>(host: &Host, cam: Rc<RefCell<T>>) -> Vec<Listener>
+where
+    T: CameraController + 'static,
+{
+    let mut listeners = Vec::new();
+    {
+        let cam = cam.clone();
+        let host_c = host.clone();
+        listeners.push(Listener::new(host.canvas_events(), "pointerdown", move |e: PointerEvent| {
+            if host_c.is_pointer_locked() {
+                e.stop_immediate_propagation();
+            } else if e.button() == 0
+                && e.pointer_type() == "mouse"
+                && cam.borrow().wants_pointer_lock()
+            {
+                host_c.lock_pointer();
+                e.stop_immediate_propagation();
+            }
+        }));
+    }
+
+    {
+        let host_c = host.clone();
+        listeners.push(Listener::new(host.window_events(), "pointermove", move |e: PointerEvent| {
+            if host_c.is_pointer_locked() {
+                cam.borrow_mut()
+                    .mouse_move(e.movement_x() as f32, e.movement_y() as f32);
+            }
+        }));
+    }
+
+    {
+        let host_c = host.clone();
+        listeners.push(Listener::new(host.window_events(), "keydown", move |e: KeyboardEvent| {
+            if e.code() == "Escape" {
+                host_c.unlock_pointer();
+            }
+        }));
+    }
+    listeners
+}
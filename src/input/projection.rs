@@ -0,0 +1,140 @@
+use glam::{Mat4, Vec3};
+use serde::{Deserialize, Serialize};
+
+use crate::scene::bounds::Aabb;
+
+/// Smallest near plane distance used by perspective projections.
+const MIN_NEAR: f32 = 0.01;
+/// Largest far/near ratio allowed when fitting the clip planes, which bounds
+/// the loss of depth precision.
+const MAX_DEPTH_RATIO: f32 = 10_000.0;
+
+#[derive(Copy, Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Projection {
+    Perspective,
+    Orthographic,
+}
+
+/// Projection settings of a camera.
+#[derive(Copy, Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Lens {
+    pub projection: Projection,
+    /// Vertical field of view in radians.
+    pub fov_y: f32,
+    pub near: f32,
+    pub far: f32,
+    /// Refit `near` and `far` to the scene bounds every frame.
+    pub auto_clip: bool,
+    /// Map the near plane to depth 1 and use an infinite far plane for
+    /// perspective. The renderer must use the matching depth test, see
+    /// `State::set_reverse_z`. Not serialized since it belongs to the
+    /// renderer setup rather than to a viewpoint.
+    #[serde(skip)]
+    pub reverse_z: bool,
+}
+
+impl Default for Lens {
+    fn default() -> Self {
+        Self {
+            projection: Projection::Perspective,
+            fov_y: std::f32::consts::FRAC_PI_4,
+            near: 0.1,
+            far: 100.0,
+            auto_clip: false,
+            reverse_z: false,
+        }
+    }
+}
+
+impl Lens {
+    /// Whether the settings give a usable projection. Lenses read from
+    /// outside, e.g. a shared URL, are checked with this since a zero field
+    /// of view or crossed clip planes make the projection singular.
+    pub fn is_valid(&self) -> bool {
+        let fov_ok = self.fov_y > 0.0 && self.fov_y < std::f32::consts::PI;
+        let planes_ok = self.near.is_finite() && self.far.is_finite() && self.far > self.near;
+        let near_ok = self.projection == Projection::Orthographic || self.near > 0.0;
+        fov_ok && planes_ok && near_ok
+    }
+
+    /// Projection matrix. `distance` is how far the camera is from the point
+    /// it looks at; the orthographic view covers the same extent there as the
+    /// perspective one, so toggling keeps the subject the same size.
+    pub fn matrix(&self, aspect: f32, distance: f32) -> Mat4 {
+        match self.projection {
+            Projection::Perspective if self.reverse_z => {
+                Mat4::perspective_infinite_reverse_lh(self.fov_y, aspect, self.near)
+            }
+            Projection::Perspective => Mat4::perspective_lh(self.fov_y, aspect, self.near, self.far),
+            Projection::Orthographic => {
+                let h = distance * (self.fov_y * 0.5).tan();
+                let w = h * aspect;
+                let (near, far) = if self.reverse_z {
+                    (self.far, self.near)
+                } else {
+                    (self.near, self.far)
+                };
+                Mat4::orthographic_lh(-w, w, -h, h, near, far)
+            }
+        }
+    }
+
+    /// Fits the clip planes tightly around `bounds` seen from `eye` looking
+    /// along `forward`.
+    pub fn fit(&mut self, eye: Vec3, forward: Vec3, bounds: &Aabb) {
+        let (mut min, mut max) = (f32::MAX, f32::MIN);
+        for c in bounds.corners() {
+            let d = (c - eye).dot(forward);
+            min = min.min(d);
+            max = max.max(d);
+        }
+        let margin = (max - min).max(MIN_NEAR) * 0.05;
+        match self.projection {
+            Projection::Perspective => {
+                self.far = (max + margin).max(MIN_NEAR * 2.0);
+                self.near = (min - margin).max(self.far / MAX_DEPTH_RATIO).max(MIN_NEAR);
+            }
+            // Orthographic depth is linear, so geometry behind the eye can
+            // stay visible with a negative near plane.
+            Projection::Orthographic => {
+                self.near = min - margin;
+                self.far = max + margin;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn unit_box() -> Aabb {
+        Aabb::new(Vec3::splat(-1.0), Vec3::splat(1.0))
+    }
+
+    #[test]
+    fn fit_encloses_the_bounds() {
+        let mut lens = Lens::default();
+        lens.fit(Vec3::new(0.0, 0.0, -5.0), Vec3::Z, &unit_box());
+        assert!(lens.near > 3.8 && lens.near < 4.0);
+        assert!(lens.far > 6.0 && lens.far < 6.2);
+        assert!(lens.is_valid());
+    }
+
+    #[test]
+    fn perspective_fit_from_inside_keeps_a_positive_near_plane() {
+        let mut lens = Lens::default();
+        lens.fit(Vec3::ZERO, Vec3::Z, &unit_box());
+        assert!(lens.near >= MIN_NEAR);
+        assert!(lens.far / lens.near <= MAX_DEPTH_RATIO * 1.001);
+    }
+
+    #[test]
+    fn orthographic_fit_reaches_behind_the_eye() {
+        let mut lens = Lens { projection: Projection::Orthographic, ..Lens::default() };
+        lens.fit(Vec3::ZERO, Vec3::Z, &unit_box());
+        assert!(lens.near < -1.0);
+        assert!(lens.far > 1.0);
+    }
+}
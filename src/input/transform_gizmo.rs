@@ -0,0 +1,404 @@
+#![cfg(target_arch = "wasm32")]
+
+use std::{cell::RefCell, rc::Rc};
+use glam::{Quat, Vec3};
+use wasm_bindgen::JsValue;
+use web_sys::PointerEvent;
+
+use crate::host::Host;
+use crate::input::camera::CameraController;
+use crate::input::listener::Listener;
+use crate::input::mouse;
+use crate::render::data::Vertex;
+use crate::scene::bounds::Ray;
+use crate::scene::object::{Scene, Transform};
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum GizmoMode {
+    Translate,
+    Rotate,
+    Scale,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum GizmoSpace {
+    World,
+    Local,
+}
+
+/// Part of the gizmo under the pointer. For `Plane` and rotation rings the
+/// index names the axis normal to the plane.
+#[derive(Copy, Clone, Debug, PartialEq)]
+enum Handle {
+    Axis(usize),
+    Plane(usize),
+    Uniform,
+}
+
+/// Snapping increments; zero disables snapping for that kind of edit.
+#[derive(Copy, Clone, Debug)]
+pub struct Snap {
+    pub translate: f32,
+    /// Rotation step in radians.
+    pub rotate: f32,
+    pub scale: f32,
+}
+
+struct Drag {
+    handle: Handle,
+    start: Transform,
+    axes: [Vec3; 3],
+    size: f32,
+    /// Point on the handle's line or plane where the drag started.
+    anchor: Vec3,
+}
+
+/// Gizmo size relative to the distance from the camera, so it keeps a
+/// roughly constant size on screen.
+const SCREEN_SIZE: f32 = 0.2;
+const PICK_WIDTH: f32 = 0.08;
+const RING_SEGMENTS: usize = 48;
+const AXIS_COLORS: [[f32; 3]; 3] = [[1.0, 0.2, 0.2], [0.2, 1.0, 0.2], [0.3, 0.5, 1.0]];
+const ACTIVE_COLOR: [f32; 3] = [1.0, 1.0, 0.2];
+
+pub struct TransformGizmo {
+    pub mode: GizmoMode,
+    pub space: GizmoSpace,
+    pub snap: Snap,
+    /// Called with the object name and its new transform after every drag step.
+    pub on_change: Option<js_sys::Function>,
+    hovered: Option<Handle>,
+    drag: Option<Drag>,
+}
+
+impl Default for TransformGizmo {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl TransformGizmo {
+    pub fn new() -> Self {
+        Self {
+            mode: GizmoMode::Translate,
+            space: GizmoSpace::World,
+            snap: Snap {
+                translate: 0.0,
+                rotate: 0.0,
+                scale: 0.0,
+            },
+            on_change: None,
+            hovered: None,
+            drag: None,
+        }
+    }
+
+    pub fn is_dragging(&self) -> bool {
+        self.drag.is_some()
+    }
+
+    fn axes(&self, t: &Transform) -> [Vec3; 3] {
+        // Non-uniform scale only makes sense along the object's own axes.
+        if self.space == GizmoSpace::Local || self.mode == GizmoMode::Scale {
+            [t.rotation * Vec3::X, t.rotation * Vec3::Y, t.rotation * Vec3::Z]
+        } else {
+            [Vec3::X, Vec3::Y, Vec3::Z]
+        }
+    }
+
+    fn size(t: &Transform, eye: Vec3) -> f32 {
+        (t.translation.distance(eye) * SCREEN_SIZE).max(1e-3)
+    }
+
+    fn hit(&self, t: &Transform, eye: Vec3, ray: &Ray) -> Option<Handle> {
+        let o = t.translation;
+        let size = Self::size(t, eye);
+        let axes = self.axes(t);
+        let width = size * PICK_WIDTH;
+        let mut best: Option<(Handle, f32)> = None;
+        let mut consider = |h: Handle, dist: f32| {
+            if best.is_none_or(|(_, d)| dist < d) {
+                best = Some((h, dist));
+            }
+        };
+        for (i, axis) in axes.iter().enumerate() {
+            match self.mode {
+                GizmoMode::Translate | GizmoMode::Scale => {
+                    if let Some((s, d)) = ray.closest_to_line(o, *axis) {
+                        if d < width && (0.0..=size).contains(&s) {
+                            consider(Handle::Axis(i), ray.origin.distance(o + *axis * s));
+                        }
+                    }
+                    if self.mode == GizmoMode::Translate {
+                        if let Some(tp) = ray.intersect_plane(o, *axis) {
+                            let p = ray.at(tp) - o;
+                            let (u, v) = (axes[(i + 1) % 3].dot(p), axes[(i + 2) % 3].dot(p));
+                            let range = size * 0.2..=size * 0.45;
+                            if range.contains(&u) && range.contains(&v) {
+                                consider(Handle::Plane(i), tp);
+                            }
+                        }
+                    }
+                }
+                GizmoMode::Rotate => {
+                    if let Some(tp) = ray.intersect_plane(o, *axis) {
+                        if (ray.at(tp).distance(o) - size).abs() < width {
+                            consider(Handle::Axis(i), tp);
+                        }
+                    }
+                }
+            }
+        }
+        if self.mode == GizmoMode::Scale {
+            let to_center = o - ray.origin;
+            let along = to_center.dot(ray.direction);
+            if along > 0.0 && (to_center - ray.direction * along).length() < width * 1.5 {
+                consider(Handle::Uniform, along);
+            }
+        }
+        best.map(|(h, _)| h)
+    }
+
+    /// Updates the highlighted handle. Returns true when it changed.
+    pub fn hover(&mut self, t: &Transform, eye: Vec3, ray: &Ray) -> bool {
+        let hovered = self.hit(t, eye, ray);
+        let changed = hovered != self.hovered;
+        self.hovered = hovered;
+        changed
+    }
+
+    /// Starts dragging the handle under `ray`. Returns false if none was hit.
+    pub fn begin(&mut self, t: &Transform, eye: Vec3, ray: &Ray) -> bool {
+        let Some(handle) = self.hit(t, eye, ray) else {
+            return false;
+        };
+        let axes = self.axes(t);
+        let o = t.translation;
+        let anchor = match (self.mode, handle) {
+            (GizmoMode::Translate | GizmoMode::Scale, Handle::Axis(i)) => {
+                o + axes[i] * ray.closest_to_line(o, axes[i]).map_or(0.0, |(s, _)| s)
+            }
+            (_, Handle::Axis(i) | Handle::Plane(i)) => {
+                ray.intersect_plane(o, axes[i]).map_or(o, |tp| ray.at(tp))
+            }
+            (_, Handle::Uniform) => ray
+                .intersect_plane(o, -ray.direction)
+                .map_or(o, |tp| ray.at(tp)),
+        };
+        self.drag = Some(Drag {
+            handle,
+            start: *t,
+            axes,
+            size: Self::size(t, eye),
+            anchor,
+        });
+        self.hovered = Some(handle);
+        true
+    }
+
+    /// Transform resulting from dragging to `ray`, or `None` when not dragging
+    /// or the pointer left the handle's constraint.
+    pub fn drag(&self, ray: &Ray) -> Option<Transform> {
+        let drag = self.drag.as_ref()?;
+        let start = drag.start;
+        let o = start.translation;
+        let mut t = start;
+        match (self.mode, drag.handle) {
+            (GizmoMode::Translate, Handle::Axis(i)) => {
+                let (s, _) = ray.closest_to_line(o, drag.axes[i])?;
+                let delta = snap(s - (drag.anchor - o).dot(drag.axes[i]), self.snap.translate);
+                t.translation = o + drag.axes[i] * delta;
+            }
+            (GizmoMode::Translate, Handle::Plane(i)) => {
+                let p = ray.at(ray.intersect_plane(o, drag.axes[i])?);
+                let delta = p - drag.anchor;
+                for axis in [drag.axes[(i + 1) % 3], drag.axes[(i + 2) % 3]] {
+                    t.translation += axis * snap(delta.dot(axis), self.snap.translate);
+                }
+            }
+            (GizmoMode::Rotate, Handle::Axis(i)) => {
+                let axis = drag.axes[i];
+                let p = ray.at(ray.intersect_plane(o, axis)?);
+                let (v0, v1) = (drag.anchor - o, p - o);
+                let angle = axis.dot(v0.cross(v1)).atan2(v0.dot(v1));
+                let angle = snap(angle, self.snap.rotate);
+                t.rotation = (Quat::from_axis_angle(axis, angle) * start.rotation).normalize();
+            }
+            (GizmoMode::Scale, Handle::Axis(i)) => {
+                let (s, _) = ray.closest_to_line(o, drag.axes[i])?;
+                let base = (drag.anchor - o).dot(drag.axes[i]).max(drag.size * 0.1);
+                let factor = snap(s.max(0.0) / base, self.snap.scale).max(0.01);
+                t.scale[i] = start.scale[i] * factor;
+            }
+            (GizmoMode::Scale, Handle::Uniform) => {
+                let p = ray.at(ray.intersect_plane(o, -ray.direction)?);
+                let base = drag.anchor.distance(o).max(drag.size * 0.1);
+                let factor = snap(p.distance(o) / base, self.snap.scale).max(0.01);
+                t.scale = start.scale * factor;
+            }
+            _ => return None,
+        }
+        Some(t)
+    }
+
+    pub fn end(&mut self) -> bool {
+        self.drag.take().is_some()
+    }
+
+    /// Line list drawing the gizmo for an object with transform `t`.
+    pub fn vertices(&self, t: &Transform, eye: Vec3) -> Vec<Vertex> {
+        let mut verts = Vec::new();
+        let o = t.translation;
+        let size = Self::size(t, eye);
+        let axes = self.axes(t);
+        let normal = [0.0, 1.0, 0.0];
+        let active = self.drag.as_ref().map(|d| d.handle).or(self.hovered);
+        let color = |h: Handle, base: [f32; 3]| if active == Some(h) { ACTIVE_COLOR } else { base };
+        let mut line = |a: Vec3, b: Vec3, color: [f32; 3]| {
+            verts.push(Vertex { position: a.into(), color, normal });
+            verts.push(Vertex { position: b.into(), color, normal });
+        };
+        for (i, axis) in axes.iter().enumerate() {
+            let (u, v) = (axes[(i + 1) % 3], axes[(i + 2) % 3]);
+            let c = color(Handle::Axis(i), AXIS_COLORS[i]);
+            match self.mode {
+                GizmoMode::Translate => {
+                    let tip = o + *axis * size;
+                    line(o, tip, c);
+                    // arrow head
+                    for side in [u, -u, v, -v] {
+                        line(tip, tip - *axis * size * 0.12 + side * size * 0.05, c);
+                    }
+                    let pc = color(Handle::Plane(i), AXIS_COLORS[i]);
+                    let (a, b) = (size * 0.2, size * 0.45);
+                    let quad = [o + u * a + v * a, o + u * b + v * a, o + u * b + v * b, o + u * a + v * b];
+                    for k in 0..4 {
+                        line(quad[k], quad[(k + 1) % 4], pc);
+                    }
+                }
+                GizmoMode::Rotate => {
+                    let point = |k: usize| {
+                        let a = k as f32 / RING_SEGMENTS as f32 * std::f32::consts::TAU;
+                        o + (u * a.cos() + v * a.sin()) * size
+                    };
+                    for k in 0..RING_SEGMENTS {
+                        line(point(k), point(k + 1), c);
+                    }
+                }
+                GizmoMode::Scale => {
+                    let tip = o + *axis * size;
+                    line(o, tip, c);
+                    let h = size * 0.05;
+                    let quad = [tip + u * h + v * h, tip - u * h + v * h, tip - u * h - v * h, tip + u * h - v * h];
+                    for k in 0..4 {
+                        line(quad[k], quad[(k + 1) % 4], c);
+                    }
+                }
+            }
+        }
+        if self.mode == GizmoMode::Scale {
+            let c = color(Handle::Uniform, [0.9, 0.9, 0.9]);
+            let h = size * PICK_WIDTH;
+            for axis in axes {
+                line(o - axis * h, o + axis * h, c);
+            }
+        }
+        verts
+    }
+}
+
+fn snap(value: f32, step: f32) -> f32 {
+    if step > 0.0 {
+        (value / step).round() * step
+    } else {
+        value
+    }
+}
+
+fn transform_to_js(t: &Transform) -> JsValue {
+    let obj = js_sys::Object::new();
+    let array = |v: &[f32]| v.iter().map(|x| JsValue::from_f64(*x as f64)).collect::<js_sys::Array>();
+    let _ = js_sys::Reflect::set(&obj, &"translation".into(), &array(&t.translation.to_array()));
+    let _ = js_sys::Reflect::set(&obj, &"rotation".into(), &array(&t.rotation.to_array()));
+    let _ = js_sys::Reflect::set(&obj, &"scale".into(), &array(&t.scale.to_array()));
+    obj.into()
+}
+
+/// Registers pointer handlers manipulating the selected object. Must be
+/// attached before `mouse::attach` so grabbing a handle does not also rotate
+/// the camera.
+pub fn attach<T>(
+    host: &Host,
+    cam: Rc<RefCell<T>>,
+    scene: Rc<RefCell<Scene>>,
+    gizmo: Rc<RefCell<TransformGizmo>>,
+) -> Vec<Listener>
+where
+    T: CameraController + 'static,
+{
+    let mut listeners = Vec::new();
+    // Grab a handle of the selected object's gizmo
+    {
+        let cam = cam.clone();
+        let scene = scene.clone();
+        let gizmo = gizmo.clone();
+        let host_c = host.clone();
+        listeners.push(Listener::new(host.canvas_events(), "pointerdown", move |e: PointerEvent| {
+            if e.button() != 0 {
+                return;
+            }
+            let scene = scene.borrow();
+            let Some(object) = scene.selected_object() else {
+                return;
+            };
+            let cam = cam.borrow();
+            let ray = cam.ray(mouse::ndc(&host_c, &e));
+            if gizmo.borrow_mut().begin(&object.transform, cam.position(), &ray) {
+                e.stop_immediate_propagation();
+            }
+        }));
+    }
+
+    // Drag the grabbed handle, or highlight the handle under the pointer
+    {
+        let cam = cam.clone();
+        let scene = scene.clone();
+        let gizmo = gizmo.clone();
+        let host_c = host.clone();
+        listeners.push(Listener::new(host.window_events(), "pointermove", move |e: PointerEvent| {
+            let changed = {
+                let mut scene = scene.borrow_mut();
+                let Some(index) = scene.selected else {
+                    return;
+                };
+                let cam = cam.borrow();
+                let ray = cam.ray(mouse::ndc(&host_c, &e));
+                let mut gizmo = gizmo.borrow_mut();
+                let object = &mut scene.objects[index];
+                if !gizmo.is_dragging() {
+                    gizmo.hover(&object.transform, cam.position(), &ray);
+                    return;
+                }
+                match gizmo.drag(&ray) {
+                    Some(t) if t != object.transform => {
+                        object.transform = t;
+                        gizmo.on_change.clone().map(|f| (f, object.name.clone(), t))
+                    }
+                    _ => None,
+                }
+            };
+            // Called without any borrows held, so it may call back into the
+            // viewer.
+            if let Some((f, name, t)) = changed {
+                let _ = f.call2(&JsValue::NULL, &JsValue::from_str(&name), &transform_to_js(&t));
+            }
+        }));
+    }
+
+    {
+        listeners.push(Listener::new(host.window_events(), "pointerup", move |_e: PointerEvent| {
+            gizmo.borrow_mut().end();
+        }));
+    }
+    listeners
+}
@@ -0,0 +1,114 @@
+#![cfg(target_arch = "wasm32")]
+
+use std::{cell::RefCell, rc::Rc};
+use glam::{Mat3, Mat4, Vec2, Vec3};
+use web_sys::PointerEvent;
+
+use crate::host::Host;
+use crate::input::camera::CameraController;
+use crate::input::listener::Listener;
+
+/// Standard viewpoints the orbit camera can snap to from the view cube.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum StandardView {
+    Top,
+    Bottom,
+    Front,
+    Back,
+    Left,
+    Right,
+    Iso,
+}
+
+impl StandardView {
+    /// Orbit yaw and pitch that place the camera on this side of the target.
+    /// The front view looks along display +Z with +X to the right; top and
+    /// bottom keep +X to the right too.
+    pub fn angles(self) -> (f32, f32) {
+        use std::f32::consts::{FRAC_PI_2, FRAC_PI_4, PI};
+        match self {
+            StandardView::Top => (-FRAC_PI_2, 1.54),
+            StandardView::Bottom => (-FRAC_PI_2, -1.54),
+            StandardView::Front => (-FRAC_PI_2, 0.0),
+            StandardView::Back => (FRAC_PI_2, 0.0),
+            StandardView::Left => (PI, 0.0),
+            StandardView::Right => (0.0, 0.0),
+            StandardView::Iso => (-FRAC_PI_4, 0.6155),
+        }
+    }
+}
+
+/// Axis tips of the triad and the view selected by clicking them.
+const TIPS: [(Vec3, StandardView); 6] = [
+    (Vec3::X, StandardView::Right),
+    (Vec3::NEG_X, StandardView::Left),
+    (Vec3::Y, StandardView::Top),
+    (Vec3::NEG_Y, StandardView::Bottom),
+    (Vec3::NEG_Z, StandardView::Front),
+    (Vec3::Z, StandardView::Back),
+];
+
+const PICK_RADIUS: f32 = 0.3;
+
+/// Corner rectangle `[x, y, width, height]` of the view cube, measured from
+/// the top-left corner in the same units as `width` and `height`.
+pub fn rect(width: f32, height: f32) -> [f32; 4] {
+    let size = (width.min(height) * 0.18).max(1.0);
+    let margin = size * 0.1;
+    [margin, height - size - margin, size, size]
+}
+
+/// Projection of the triad: only the camera rotation, without translation.
+pub fn matrix(view: Mat4) -> Mat4 {
+    let rotation = Mat4::from_mat3(Mat3::from_mat4(view));
+    Mat4::orthographic_lh(-1.5, 1.5, -1.5, 1.5, -2.0, 2.0) * rotation
+}
+
+/// Picks the view under the point `(x, y)` given in the cube rectangle's
+/// normalized coordinates (`-1..1`, y up).
+pub fn pick(view: Mat4, x: f32, y: f32) -> Option<StandardView> {
+    let m = matrix(view);
+    let p = Vec2::new(x, y);
+    let hit = TIPS
+        .iter()
+        .map(|(dir, v)| (m.project_point3(*dir), *v))
+        .filter(|(tip, _)| tip.truncate().distance(p) < PICK_RADIUS)
+        // Prefer the tip closest to the viewer when two overlap.
+        .min_by(|a, b| a.0.z.total_cmp(&b.0.z))
+        .map(|(_, v)| v);
+    hit.or_else(|| (p.length() < PICK_RADIUS).then_some(StandardView::Iso))
+}
+
+/// Registers the click handler of the view cube. Must be attached before
+/// `mouse::attach` so a click on the cube does not start a camera drag.
+pub fn attach<T>(host: &Host, cam: Rc<RefCell<T>>) -> Vec<Listener>
+where
+    T: CameraController + 'static,
+{
+    let mut listeners = Vec::new();
+    let host_c = host.clone();
+    listeners.push(Listener::new(host.canvas_events(), "pointerdown", move |e: PointerEvent| {
+        if e.button() != 0 {
+            return;
+        }
+        // The cube sits in the corner of the viewport under the pointer.
+        let [vx, vy, vw, vh] = cam.borrow().viewport();
+        let Vec2 { x: width, y: height } = host_c.css_size();
+        let (vx, vy, vw, vh) = (vx * width, vy * height, vw * width, vh * height);
+        let [rx, ry, rw, rh] = rect(vw, vh);
+        let (rx, ry) = (vx + rx, vy + ry);
+        let x = (e.offset_x() as f32 - rx) / rw * 2.0 - 1.0;
+        let y = 1.0 - (e.offset_y() as f32 - ry) / rh * 2.0;
+        if x.abs() > 1.0 || y.abs() > 1.0 {
+            return;
+        }
+        // The triad shows world axes, which land on the same spots as the
+        // display axes the tips are defined in.
+        let view = cam.borrow().display_view();
+        if let Some(v) = pick(view, x, y) {
+            cam.borrow_mut().snap_to_view(v);
+            e.stop_immediate_propagation();
+        }
+    }));
+    listeners
+}
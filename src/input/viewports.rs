@@ -0,0 +1,330 @@
+#![cfg(target_arch = "wasm32")]
+
+use std::{cell::RefCell, collections::HashSet, rc::Rc};
+use glam::{Mat4, Vec2, Vec3};
+use web_sys::{MouseEvent, PointerEvent, WheelEvent};
+
+use crate::host::Host;
+use crate::input::active_camera::ActiveCamera;
+use crate::input::bindings::Action;
+use crate::input::camera::CameraController;
+use crate::input::listener::Listener;
+use crate::input::gamepad::AnalogInput;
+use crate::input::projection::{Lens, Projection};
+use crate::input::view_cube::StandardView;
+use crate::scene::bounds::{Aabb, Ray};
+use crate::scene::collision::Triangle;
+
+/// Arrangement of the viewports on the canvas.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Layout {
+    /// One perspective view over the whole canvas.
+    Single,
+    /// Orthographic top, front and side views with a perspective view in
+    /// the bottom-right quarter.
+    Quad,
+    /// Two independent perspective views side by side.
+    Split,
+}
+
+impl Layout {
+    /// Rectangles `[x, y, width, height]` as fractions of the canvas from
+    /// the top-left corner, with the standard view of each orthographic
+    /// viewport. The last viewport is the main perspective view.
+    fn views(self) -> Vec<([f32; 4], Option<StandardView>)> {
+        match self {
+            Layout::Single => vec![([0.0, 0.0, 1.0, 1.0], None)],
+            Layout::Quad => vec![
+                ([0.0, 0.0, 0.5, 0.5], Some(StandardView::Top)),
+                ([0.5, 0.0, 0.5, 0.5], Some(StandardView::Front)),
+                ([0.0, 0.5, 0.5, 0.5], Some(StandardView::Right)),
+                ([0.5, 0.5, 0.5, 0.5], None),
+            ],
+            Layout::Split => vec![([0.0, 0.0, 0.5, 1.0], None), ([0.5, 0.0, 0.5, 1.0], None)],
+        }
+    }
+}
+
+/// A camera drawn into part of the canvas.
+pub struct Viewport {
+    pub camera: ActiveCamera,
+    /// `[x, y, width, height]` as fractions of the canvas, from the top left.
+    pub rect: [f32; 4],
+}
+
+impl Viewport {
+    /// Converts canvas NDC into this viewport's NDC.
+    fn local_ndc(&self, ndc: Vec2) -> Vec2 {
+        let [x, y, w, h] = self.rect;
+        let (fx, fy) = ((ndc.x + 1.0) * 0.5, (1.0 - ndc.y) * 0.5);
+        Vec2::new((fx - x) / w * 2.0 - 1.0, 1.0 - (fy - y) / h * 2.0)
+    }
+
+    fn contains(&self, fx: f32, fy: f32) -> bool {
+        let [x, y, w, h] = self.rect;
+        (x..x + w).contains(&fx) && (y..y + h).contains(&fy)
+    }
+}
+
+/// Cameras of all viewports. Input goes to the active viewport, the one last
+/// under the pointer; coordinates passed in are relative to the whole canvas.
+pub struct Viewports {
+    views: Vec<Viewport>,
+    active: usize,
+    layout: Layout,
+    aspect: f32,
+    /// Held actions, moved along when the active viewport changes.
+    pressed: HashSet<Action>,
+}
+
+impl Viewports {
+    pub fn new(aspect: f32) -> Self {
+        Self {
+            views: vec![Viewport {
+                camera: ActiveCamera::new(aspect),
+                rect: [0.0, 0.0, 1.0, 1.0],
+            }],
+            active: 0,
+            layout: Layout::Single,
+            aspect,
+            pressed: HashSet::new(),
+        }
+    }
+
+    pub fn layout(&self) -> Layout {
+        self.layout
+    }
+
+    /// Rearranges the canvas. The main perspective camera is kept, the other
+    /// viewports get new cameras set up like it.
+    pub fn set_layout(&mut self, layout: Layout) {
+        if layout == self.layout {
+            return;
+        }
+        self.release_actions();
+        let main = self.views.pop().unwrap().camera;
+        let mut views = layout.views();
+        let (main_rect, _) = views.pop().unwrap();
+        self.views = views
+            .into_iter()
+            .map(|(rect, standard)| {
+                let mut camera = ActiveCamera::new(self.aspect * rect[2] / rect[3]);
+                camera.set_world_frame(main.world_frame());
+                camera.set_collision(main.collision());
+                let lens = *main.lens();
+                camera.update_lenses(|l| {
+                    l.auto_clip = lens.auto_clip;
+                    l.reverse_z = lens.reverse_z;
+                });
+                match standard {
+                    Some(view) => {
+                        camera.snap_to_view(view);
+                        camera.lens_mut().projection = Projection::Orthographic;
+                    }
+                    None => camera.set_state(&main.state(), false),
+                }
+                Viewport { camera, rect }
+            })
+            .collect();
+        self.views.push(Viewport {
+            camera: main,
+            rect: main_rect,
+        });
+        self.layout = layout;
+        self.active = self.views.len() - 1;
+        self.set_aspect(self.aspect);
+    }
+
+    /// Replaces the world-space geometry walk cameras collide with.
+    pub fn set_collision(&mut self, triangles: Vec<Triangle>) {
+        let triangles: Rc<[Triangle]> = triangles.into();
+        for view in &mut self.views {
+            view.camera.set_collision(triangles.clone());
+        }
+    }
+
+    pub fn views(&self) -> &[Viewport] {
+        &self.views
+    }
+
+    pub fn views_mut(&mut self) -> &mut [Viewport] {
+        &mut self.views
+    }
+
+    pub fn rects(&self) -> Vec<[f32; 4]> {
+        self.views.iter().map(|v| v.rect).collect()
+    }
+
+    pub fn active_mut(&mut self) -> &mut ActiveCamera {
+        &mut self.views[self.active].camera
+    }
+
+    /// The perspective camera that paths, bookmarks in the URL and the
+    /// single layout use.
+    pub fn main(&self) -> &ActiveCamera {
+        &self.views.last().unwrap().camera
+    }
+
+    pub fn main_mut(&mut self) -> &mut ActiveCamera {
+        &mut self.views.last_mut().unwrap().camera
+    }
+
+    /// Makes the viewport under canvas NDC `ndc` active.
+    pub fn focus(&mut self, ndc: Vec2) {
+        let (fx, fy) = ((ndc.x + 1.0) * 0.5, (1.0 - ndc.y) * 0.5);
+        let Some(index) = self.views.iter().position(|v| v.contains(fx, fy)) else {
+            return;
+        };
+        if index != self.active {
+            let held: Vec<Action> = self.pressed.iter().copied().collect();
+            self.release_actions();
+            self.active = index;
+            for action in held {
+                self.action_down(action);
+            }
+        }
+    }
+
+    /// Releases all held actions, e.g. before the keys they are bound to
+    /// change.
+    pub fn release_actions(&mut self) {
+        let camera = &mut self.views[self.active].camera;
+        for action in self.pressed.drain() {
+            camera.action_up(action);
+        }
+    }
+
+    /// Sets the aspect ratio of the whole canvas.
+    pub fn set_aspect(&mut self, aspect: f32) {
+        self.aspect = aspect;
+        for view in &mut self.views {
+            view.camera.set_aspect(aspect * view.rect[2] / view.rect[3]);
+        }
+    }
+
+    fn active_view(&self) -> &Viewport {
+        &self.views[self.active]
+    }
+}
+
+impl CameraController for Viewports {
+    fn action_down(&mut self, action: Action) {
+        self.pressed.insert(action);
+        self.active_mut().action_down(action);
+    }
+
+    fn action_up(&mut self, action: Action) {
+        self.pressed.remove(&action);
+        self.active_mut().action_up(action);
+    }
+
+    fn mouse_move(&mut self, dx: f32, dy: f32) {
+        self.active_mut().mouse_move(dx, dy);
+    }
+
+    fn pan(&mut self, dx: f32, dy: f32) {
+        let h = self.active_view().rect[3];
+        self.active_mut().pan(dx / h, dy / h);
+    }
+
+    fn zoom(&mut self, amount: f32, ndc: Vec2) {
+        let ndc = self.active_view().local_ndc(ndc);
+        self.active_mut().zoom(amount, ndc);
+    }
+
+    fn analog(&mut self, input: &AnalogInput, dt: f32) {
+        self.active_mut().analog(input, dt);
+    }
+
+    fn snap_to_view(&mut self, view: StandardView) {
+        self.active_mut().snap_to_view(view);
+    }
+
+    fn wants_pointer_lock(&self) -> bool {
+        self.active_view().camera.wants_pointer_lock()
+    }
+
+    fn lens(&self) -> &Lens {
+        self.active_view().camera.lens()
+    }
+
+    fn lens_mut(&mut self) -> &mut Lens {
+        self.active_mut().lens_mut()
+    }
+
+    fn update(&mut self, dt: f32) {
+        for view in &mut self.views {
+            view.camera.update(dt);
+        }
+    }
+
+    fn view(&self) -> Mat4 {
+        self.active_view().camera.view()
+    }
+
+    fn matrix(&self) -> Mat4 {
+        self.active_view().camera.matrix()
+    }
+
+    fn position(&self) -> Vec3 {
+        self.active_view().camera.position()
+    }
+
+    fn display_view(&self) -> Mat4 {
+        self.active_view().camera.display_view()
+    }
+
+    fn viewport(&self) -> [f32; 4] {
+        self.active_view().rect
+    }
+
+    fn ray(&self, ndc: Vec2) -> Ray {
+        let view = self.active_view();
+        view.camera.ray(view.local_ndc(ndc))
+    }
+
+    fn drag_end(&mut self) {
+        self.active_mut().drag_end();
+    }
+
+    fn is_animating(&self) -> bool {
+        self.views.iter().any(|v| v.camera.is_animating())
+    }
+
+    fn fit_clip_planes(&mut self, bounds: &Aabb) {
+        self.active_mut().fit_clip_planes(bounds);
+    }
+}
+
+/// Routes input to the viewport under the pointer. Must be attached before
+/// the other canvas handlers so they see the right viewport active. A drag
+/// keeps its viewport until all buttons are released.
+pub fn attach(host: &Host, cam: Rc<RefCell<Viewports>>) -> Vec<Listener> {
+    let mut listeners = Vec::new();
+    let focus = {
+        let host = host.clone();
+        move |cam: &RefCell<Viewports>, e: &MouseEvent| {
+            cam.borrow_mut().focus(crate::input::mouse::ndc(&host, e));
+        }
+    };
+    {
+        let cam = cam.clone();
+        let focus = focus.clone();
+        listeners.push(Listener::new(host.canvas_events(), "pointermove", move |e: PointerEvent| {
+            if e.buttons() == 0 {
+                focus(&cam, &e);
+            }
+        }));
+    }
+    {
+        let cam = cam.clone();
+        let focus = focus.clone();
+        listeners.push(Listener::new(host.canvas_events(), "pointerdown", move |e: PointerEvent| {
+            focus(&cam, &e);
+        }));
+    }
+    listeners.push(Listener::new(host.canvas_events(), "wheel", move |e: WheelEvent| {
+        focus(&cam, &e);
+    }));
+    listeners
+}
@@ -0,0 +1,248 @@
+use glam::{Mat4, Vec3};
+use std::collections::HashSet;
+
+use crate::input::bindings::Action;
+use crate::input::camera::{CameraController, LOOK_RATE};
+use crate::input::camera_state::WalkState;
+use crate::input::gamepad::AnalogInput;
+use crate::input::inertia::{self, Inertia};
+use crate::input::projection::Lens;
+use crate::scene::collision::{self, Capsule, Collider};
+
+/// Height of the ground plane used where there is no geometry below.
+const GROUND_HEIGHT: f32 = 0.0;
+/// Ledges up to this height are stepped onto instead of blocking.
+const STEP_HEIGHT: f32 = 0.35;
+/// Space between the eye and the top of the collision capsule.
+const HEAD_ROOM: f32 = 0.1;
+
+/// First-person camera that walks on the scene: the eye stays `eye_height`
+/// above the floor, gravity pulls it down and a capsule keeps it out of
+/// walls. Works in the Y-up display frame.
+pub struct WalkCamera {
+    /// Point on the floor below the eye.
+    feet: Vec3,
+    yaw: f32,
+    pitch: f32,
+    pub speed: f32,
+    pub sensitivity: f32,
+    pub eye_height: f32,
+    /// Radius of the collision capsule.
+    pub radius: f32,
+    pub gravity: f32,
+    /// Upward speed at the start of a jump.
+    pub jump_speed: f32,
+    /// Distance whose extent the orthographic projection shows.
+    pub focus_distance: f32,
+    pub lens: Lens,
+    pub inertia: Inertia,
+    pressed: HashSet<Action>,
+    aspect: f32,
+    velocity: Vec3,
+    vertical_speed: f32,
+    on_floor: bool,
+    collider: Collider,
+}
+
+impl WalkCamera {
+    pub fn new(aspect: f32) -> Self {
+        Self {
+            feet: Vec3::new(0.0, GROUND_HEIGHT, 3.0),
+            yaw: -std::f32::consts::FRAC_PI_2,
+            pitch: 0.0,
+            speed: 1.5,
+            sensitivity: 0.002,
+            eye_height: 1.6,
+            radius: 0.3,
+            gravity: 9.81,
+            jump_speed: 4.0,
+            focus_distance: 2.0,
+            lens: Lens::default(),
+            inertia: Inertia::default(),
+            pressed: HashSet::new(),
+            aspect,
+            velocity: Vec3::ZERO,
+            vertical_speed: 0.0,
+            on_floor: false,
+            collider: Collider::default(),
+        }
+    }
+
+    pub fn set_aspect(&mut self, aspect: f32) {
+        self.aspect = aspect;
+    }
+
+    /// Replaces the geometry walked on and collided with.
+    pub fn set_collision(&mut self, collider: Collider) {
+        self.collider = collider;
+    }
+
+    /// Puts the eye at `position` looking along `forward`. The camera falls
+    /// from there if it is above the floor.
+    pub fn look_from(&mut self, position: Vec3, forward: Vec3, distance: f32) {
+        self.feet = position - Vec3::Y * self.eye_height;
+        self.yaw = forward.z.atan2(forward.x);
+        self.pitch = forward.y.clamp(-1.0, 1.0).asin().clamp(-1.54, 1.54);
+        self.focus_distance = distance;
+        self.velocity = Vec3::ZERO;
+        self.vertical_speed = 0.0;
+        self.on_floor = false;
+    }
+
+    pub fn state(&self) -> WalkState {
+        WalkState {
+            feet: self.feet,
+            yaw: self.yaw,
+            pitch: self.pitch,
+            lens: self.lens,
+        }
+    }
+
+    pub fn set_state(&mut self, state: &WalkState) {
+        self.feet = state.feet;
+        self.yaw = state.yaw;
+        self.pitch = state.pitch.clamp(-1.54, 1.54);
+        self.lens = Lens {
+            reverse_z: self.lens.reverse_z,
+            ..state.lens
+        };
+        self.velocity = Vec3::ZERO;
+        self.vertical_speed = 0.0;
+    }
+
+    pub fn mouse_move(&mut self, dx: f32, dy: f32) {
+        self.yaw -= dx * self.sensitivity;
+        self.pitch = (self.pitch + dy * self.sensitivity).clamp(-1.54, 1.54);
+    }
+
+    pub fn analog(&mut self, input: &AnalogInput, dt: f32) {
+        let (forward, right) = self.ground_axes();
+        self.feet += (forward * input.movement.y + right * input.movement.x) * self.speed * dt;
+        self.yaw -= input.look.x * LOOK_RATE * dt;
+        self.pitch = (self.pitch + input.look.y * LOOK_RATE * dt).clamp(-1.54, 1.54);
+    }
+
+    pub fn update(&mut self, dt: f32) {
+        let (forward, right) = self.ground_axes();
+        // Normalized so diagonals are no faster on foot.
+        let wish = inertia::wish_velocity(&self.pressed, forward, right, 1.0).normalize_or_zero();
+        let rate = self.inertia.rate(wish != Vec3::ZERO);
+        self.velocity = inertia::approach(self.velocity, wish * self.speed, rate, dt);
+
+        if self.on_floor && self.pressed.contains(&Action::Jump) {
+            self.vertical_speed = self.jump_speed;
+            self.on_floor = false;
+        }
+        self.vertical_speed -= self.gravity * dt;
+        self.feet += self.velocity * dt + Vec3::Y * self.vertical_speed * dt;
+
+        // Walls. The capsule starts above step height so low ledges are left
+        // to the floor check below and can be walked onto.
+        let mut capsule = Capsule {
+            bottom: self.feet + Vec3::Y * (STEP_HEIGHT + self.radius),
+            top: self.feet + Vec3::Y * (self.eye_height + HEAD_ROOM - self.radius),
+            radius: self.radius,
+        };
+        let contact = capsule.resolve(&self.collider);
+        self.feet += contact.offset;
+        if contact.offset.y < 0.0 {
+            // Hit the ceiling.
+            self.vertical_speed = self.vertical_speed.min(0.0);
+        }
+
+        let floor = collision::floor_below(self.feet + Vec3::Y * STEP_HEIGHT, &self.collider)
+            .unwrap_or(GROUND_HEIGHT);
+        let above = self.feet.y - floor;
+        // Stick to the floor when walking down steps and slopes.
+        let snap = self.on_floor && self.vertical_speed <= 0.0 && above <= STEP_HEIGHT;
+        if above <= 0.0 || snap {
+            self.feet.y = floor;
+            self.vertical_speed = 0.0;
+            self.on_floor = true;
+        } else {
+            // Resting on an edge or slope the floor ray missed, e.g. with
+            // the feet just past a ledge.
+            self.on_floor = contact.on_floor;
+            if contact.on_floor {
+                self.vertical_speed = self.vertical_speed.max(0.0);
+            }
+        }
+    }
+
+    /// Horizontal forward and right directions.
+    fn ground_axes(&self) -> (Vec3, Vec3) {
+        let forward = Vec3::new(self.yaw.cos(), 0.0, self.yaw.sin());
+        (forward, Vec3::Y.cross(forward))
+    }
+
+    fn forward(&self) -> Vec3 {
+        Vec3::new(
+            self.yaw.cos() * self.pitch.cos(),
+            self.pitch.sin(),
+            self.yaw.sin() * self.pitch.cos(),
+        )
+    }
+
+    pub fn position(&self) -> Vec3 {
+        self.feet + Vec3::Y * self.eye_height
+    }
+
+    pub fn view(&self) -> Mat4 {
+        let eye = self.position();
+        Mat4::look_at_lh(eye, eye + self.forward(), Vec3::Y)
+    }
+
+    pub fn matrix(&self) -> Mat4 {
+        self.lens.matrix(self.aspect, self.focus_distance) * self.view()
+    }
+}
+
+impl CameraController for WalkCamera {
+    fn action_down(&mut self, action: Action) {
+        self.pressed.insert(action);
+    }
+
+    fn action_up(&mut self, action: Action) {
+        self.pressed.remove(&action);
+    }
+
+    fn mouse_move(&mut self, dx: f32, dy: f32) {
+        WalkCamera::mouse_move(self, dx, dy);
+    }
+
+    fn analog(&mut self, input: &AnalogInput, dt: f32) {
+        WalkCamera::analog(self, input, dt);
+    }
+
+    fn wants_pointer_lock(&self) -> bool {
+        true
+    }
+
+    fn is_animating(&self) -> bool {
+        !self.pressed.is_empty() || !self.on_floor || self.velocity.length() > inertia::REST_SPEED
+    }
+
+    fn lens(&self) -> &Lens {
+        &self.lens
+    }
+
+    fn lens_mut(&mut self) -> &mut Lens {
+        &mut self.lens
+    }
+
+    fn update(&mut self, dt: f32) {
+        WalkCamera::update(self, dt);
+    }
+
+    fn view(&self) -> Mat4 {
+        WalkCamera::view(self)
+    }
+
+    fn matrix(&self) -> Mat4 {
+        WalkCamera::matrix(self)
+    }
+
+    fn position(&self) -> Vec3 {
+        WalkCamera::position(self)
+    }
+}
@@ -1,6 +1,10 @@
 #[cfg(target_arch = "wasm32")]
+pub mod host;
 pub mod input;
 #[cfg(target_arch = "wasm32")]
 pub mod render;
 #[cfg(target_arch = "wasm32")]
+pub mod resize;
+pub mod scene;
+#[cfg(target_arch = "wasm32")]
 pub mod web;
@@ -0,0 +1,29 @@
+struct OutlineUniforms {
+    mvp: mat4x4<f32>,
+    color: vec4<f32>,
+    viewport: vec2<f32>,
+    width: f32,
+    _pad: f32,
+};
+
+@group(0) @binding(0) var<uniform> outline: OutlineUniforms;
+
+@vertex
+fn vs_outline(@location(0) position: vec3<f32>) -> @builtin(position) vec4<f32> {
+    var clip = outline.mvp * vec4<f32>(position, 1.0);
+    let center = outline.mvp * vec4<f32>(0.0, 0.0, 0.0, 1.0);
+    // Push vertices away from the object's centre on screen. Unlike face
+    // normals this direction is shared by coincident vertices, so hard edges
+    // do not open gaps in the silhouette.
+    let dir = clip.xy / clip.w - center.xy / center.w;
+    if (length(dir) > 1e-6) {
+        let offset = normalize(dir) * outline.width * 2.0 / outline.viewport;
+        clip = vec4<f32>(clip.xy + offset * clip.w, clip.zw);
+    }
+    return clip;
+}
+
+@fragment
+fn fs_outline() -> @location(0) vec4<f32> {
+    return outline.color;
+}
@@ -202,6 +202,16 @@ pub struct SceneUniforms {
     pub lights: [Light; 2],
 }
 
+#[repr(C)]
+#[derive(Clone, Copy)]
+pub struct OutlineUniforms {
+    pub mvp: [[f32; 4]; 4],
+    pub color: [f32; 4],
+    pub viewport: [f32; 2],
+    pub width: f32,
+    pub _pad: f32,
+}
+
 pub fn grid_vertices(size: i32) -> Vec<Vertex> {
     let mut verts = Vec::new();
     let color = [0.3, 0.3, 0.3];
@@ -237,3 +247,36 @@ pub fn light_rays(lights: &[Light]) -> Vec<Vertex> {
     verts
 }
 
+pub fn axis_triad() -> Vec<Vertex> {
+    let mut verts = Vec::new();
+    let normal = [0.0_f32, 1.0, 0.0];
+    let axes = [
+        ([1.0_f32, 0.0, 0.0], [0.0_f32, 1.0, 0.0], [1.0_f32, 0.2, 0.2]),
+        ([0.0, 1.0, 0.0], [0.0, 0.0, 1.0], [0.2, 1.0, 0.2]),
+        ([0.0, 0.0, 1.0], [1.0, 0.0, 0.0], [0.3, 0.5, 1.0]),
+    ];
+    let tick = 0.12_f32;
+    for (dir, side, color) in axes {
+        let dim = [color[0] * 0.4, color[1] * 0.4, color[2] * 0.4];
+        // positive half in full colour, negative half dimmed
+        verts.push(Vertex { position: [0.0, 0.0, 0.0], color, normal });
+        verts.push(Vertex { position: dir, color, normal });
+        verts.push(Vertex { position: [0.0, 0.0, 0.0], color: dim, normal });
+        verts.push(Vertex { position: [-dir[0], -dir[1], -dir[2]], color: dim, normal });
+        // small ticks marking the clickable tips
+        for (s, c) in [(1.0_f32, color), (-1.0, dim)] {
+            let tip = [dir[0] * s, dir[1] * s, dir[2] * s];
+            verts.push(Vertex {
+                position: [tip[0] - side[0] * tick, tip[1] - side[1] * tick, tip[2] - side[2] * tick],
+                color: c,
+                normal,
+            });
+            verts.push(Vertex {
+                position: [tip[0] + side[0] * tick, tip[1] + side[1] * tick, tip[2] + side[2] * tick],
+                color: c,
+                normal,
+            });
+        }
+    }
+    verts
+}
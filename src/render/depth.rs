@@ -2,6 +2,38 @@
 
 use wgpu::{Device, Texture, TextureFormat, TextureView};
 
+/// Depth buffer format. The stencil aspect masks selected objects for the
+/// outline pass. Reversed depth only gains precision with a float buffer,
+/// so it uses one where the device has the feature; see `FEATURES`.
+pub fn format(device: &Device, reverse_z: bool) -> TextureFormat {
+    if reverse_z && device.features().contains(FEATURES) {
+        TextureFormat::Depth32FloatStencil8
+    } else {
+        TextureFormat::Depth24PlusStencil8
+    }
+}
+
+/// Optional features the depth buffer uses when the adapter has them.
+pub const FEATURES: wgpu::Features = wgpu::Features::DEPTH32FLOAT_STENCIL8;
+
+/// Depth test passing for fragments closer to the camera.
+pub fn compare(reverse_z: bool) -> wgpu::CompareFunction {
+    if reverse_z {
+        wgpu::CompareFunction::Greater
+    } else {
+        wgpu::CompareFunction::Less
+    }
+}
+
+/// Depth the buffer is cleared to, i.e. the far plane.
+pub fn clear_value(reverse_z: bool) -> f32 {
+    if reverse_z {
+        0.0
+    } else {
+        1.0
+    }
+}
+
 pub fn create(device: &Device, w: u32, h: u32, format: TextureFormat) -> (Texture, TextureView) {
     let texture = device.create_texture(&wgpu::TextureDescriptor {
         label: Some("depth texture"),
@@ -0,0 +1,152 @@
+#![cfg(target_arch = "wasm32")]
+
+use std::cell::RefCell;
+use std::rc::{Rc, Weak};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use wasm_bindgen::JsValue;
+
+use crate::render::depth;
+use web_sys::{HtmlCanvasElement, OffscreenCanvas};
+
+thread_local! {
+    static SHARED: RefCell<Option<Gpu>> = const { RefCell::new(None) };
+    static LOST_LISTENERS: RefCell<Vec<Weak<dyn Fn()>>> = const { RefCell::new(Vec::new()) };
+}
+
+/// Keeps a function registered with `on_device_lost`; dropping it
+/// unregisters the function.
+pub struct LostListener {
+    _f: Rc<dyn Fn()>,
+}
+
+/// Calls `f` whenever a device is lost, so viewers that only draw on demand
+/// wake up and replace their renderer.
+pub fn on_device_lost(f: impl Fn() + 'static) -> LostListener {
+    let f: Rc<dyn Fn()> = Rc::new(f);
+    LOST_LISTENERS.with(|l| l.borrow_mut().push(Rc::downgrade(&f)));
+    LostListener { _f: f }
+}
+
+/// Marks the device behind `lost` as lost and notifies the listeners.
+pub fn set_lost(lost: &AtomicBool) {
+    lost.store(true, Ordering::Relaxed);
+    // Collected first since listeners may register new ones.
+    let listeners: Vec<_> = LOST_LISTENERS.with(|l| {
+        let mut l = l.borrow_mut();
+        l.retain(|f| f.strong_count() > 0);
+        l.iter().filter_map(Weak::upgrade).collect()
+    });
+    for f in listeners {
+        f();
+    }
+}
+
+/// Canvas a viewer draws into: one on the page, or one transferred to a
+/// worker.
+#[derive(Clone)]
+pub enum Canvas {
+    Html(HtmlCanvasElement),
+    Offscreen(OffscreenCanvas),
+}
+
+impl Canvas {
+    /// Size of the drawing buffer in device pixels.
+    pub fn size(&self) -> (u32, u32) {
+        match self {
+            Canvas::Html(c) => (c.width(), c.height()),
+            Canvas::Offscreen(c) => (c.width(), c.height()),
+        }
+    }
+
+    fn target(&self) -> wgpu::SurfaceTarget<'static> {
+        match self {
+            Canvas::Html(c) => wgpu::SurfaceTarget::Canvas(c.clone()),
+            Canvas::Offscreen(c) => wgpu::SurfaceTarget::OffscreenCanvas(c.clone()),
+        }
+    }
+}
+
+/// Adapter and device shared by every viewer on the page.
+#[derive(Clone)]
+pub struct Gpu {
+    pub instance: wgpu::Instance,
+    pub adapter: wgpu::Adapter,
+    pub device: wgpu::Device,
+    pub queue: wgpu::Queue,
+    /// Set once the device is lost, e.g. after a driver crash or update.
+    pub lost: Arc<AtomicBool>,
+}
+
+impl Gpu {
+    pub fn is_lost(&self) -> bool {
+        self.lost.load(Ordering::Relaxed)
+    }
+
+    /// Creates a surface for `canvas` on the shared device, requesting the
+    /// device first if no viewer has done so yet or the last one was lost.
+    pub async fn surface(canvas: &Canvas) -> Result<(Gpu, wgpu::Surface<'static>), JsValue> {
+        let shared = SHARED.with(|s| s.borrow().clone()).filter(|gpu| !gpu.is_lost());
+        if let Some(gpu) = shared {
+            let surface = create_surface(&gpu.instance, canvas)?;
+            return Ok((gpu, surface));
+        }
+        let instance = wgpu::Instance::default();
+        let surface = create_surface(&instance, canvas)?;
+        let adapter = instance
+            .request_adapter(&wgpu::RequestAdapterOptions {
+                power_preference: wgpu::PowerPreference::HighPerformance,
+                compatible_surface: Some(&surface),
+                force_fallback_adapter: false,
+            })
+            .await
+            .map_err(|e| JsValue::from_str(&e.to_string()))?;
+        let (device, queue) = adapter
+            .request_device(
+                &wgpu::DeviceDescriptor {
+                    label: None,
+                    required_features: adapter.features() & depth::FEATURES,
+                    required_limits: adapter.limits(),
+                    memory_hints: wgpu::MemoryHints::default(),
+                    trace: wgpu::Trace::default(),
+                },
+            )
+            .await
+            .map_err(|e| JsValue::from_str(&e.to_string()))?;
+        let lost = Arc::new(AtomicBool::new(false));
+        {
+            let lost = lost.clone();
+            // Called on this thread: wasm has no other.
+            device.set_device_lost_callback(move |_, _| set_lost(&lost));
+        }
+        // Another viewer may have finished first while this one was waiting;
+        // its device wins so later viewers still share one.
+        let gpu = SHARED.with(|s| {
+            let mut shared = s.borrow_mut();
+            match &*shared {
+                Some(gpu) if !gpu.is_lost() => gpu.clone(),
+                _ => shared
+                    .insert(Gpu {
+                        instance,
+                        adapter,
+                        device,
+                        queue,
+                        lost,
+                    })
+                    .clone(),
+            }
+        });
+        let surface = create_surface(&gpu.instance, canvas)?;
+        Ok((gpu, surface))
+    }
+}
+
+fn create_surface(
+    instance: &wgpu::Instance,
+    canvas: &Canvas,
+) -> Result<wgpu::Surface<'static>, JsValue> {
+    let surface = instance
+        .create_surface(canvas.target())
+        .map_err(|e| JsValue::from_str(&format!("{e:?}")))?;
+    Ok(unsafe { std::mem::transmute::<wgpu::Surface<'_>, wgpu::Surface<'static>>(surface) })
+}
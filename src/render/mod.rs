@@ -2,5 +2,6 @@
 
 pub mod data;
 pub mod depth;
+pub mod gpu;
 pub mod pipeline;
 pub mod state;
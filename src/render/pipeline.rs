@@ -3,7 +3,28 @@
 use crate::render::data::Vertex;
 use wgpu::{BindGroupLayout, Device, RenderPipeline, TextureFormat};
 
-pub fn build(device: &Device, format: TextureFormat, layout: &BindGroupLayout) -> RenderPipeline {
+const STENCIL_REPLACE: wgpu::StencilFaceState = wgpu::StencilFaceState {
+    compare: wgpu::CompareFunction::Always,
+    fail_op: wgpu::StencilOperation::Keep,
+    depth_fail_op: wgpu::StencilOperation::Keep,
+    pass_op: wgpu::StencilOperation::Replace,
+};
+
+const STENCIL_OUTSIDE: wgpu::StencilFaceState = wgpu::StencilFaceState {
+    compare: wgpu::CompareFunction::NotEqual,
+    fail_op: wgpu::StencilOperation::Keep,
+    depth_fail_op: wgpu::StencilOperation::Keep,
+    pass_op: wgpu::StencilOperation::Keep,
+};
+
+pub fn build(
+    device: &Device,
+    format: TextureFormat,
+    layout: &BindGroupLayout,
+    depth_format: TextureFormat,
+    depth_compare: wgpu::CompareFunction,
+    front_face: wgpu::FrontFace,
+) -> RenderPipeline {
     let shader = device.create_shader_module(wgpu::include_wgsl!("../shader.wgsl"));
     let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
         label: Some("pipeline layout"),
@@ -32,14 +53,21 @@ pub fn build(device: &Device, format: TextureFormat, layout: &BindGroupLayout) -
         primitive: wgpu::PrimitiveState {
             topology: wgpu::PrimitiveTopology::TriangleList,
             cull_mode: Some(wgpu::Face::Front),
-            front_face: wgpu::FrontFace::Ccw,
+            front_face,
             ..Default::default()
         },
         depth_stencil: Some(wgpu::DepthStencilState {
-            format: wgpu::TextureFormat::Depth32Float,
+            format: depth_format,
             depth_write_enabled: true,
-            depth_compare: wgpu::CompareFunction::Less,
-            stencil: Default::default(),
+            depth_compare,
+            // Tag covered pixels with the draw's stencil reference so the
+            // outline pass can skip the object's own silhouette.
+            stencil: wgpu::StencilState {
+                front: STENCIL_REPLACE,
+                back: STENCIL_REPLACE,
+                read_mask: 0xff,
+                write_mask: 0xff,
+            },
             bias: Default::default(),
         }),
         multisample: wgpu::MultisampleState::default(),
@@ -48,15 +76,42 @@ pub fn build(device: &Device, format: TextureFormat, layout: &BindGroupLayout) -
     })
 }
 
-pub fn build_lines(device: &Device, format: TextureFormat, layout: &BindGroupLayout) -> RenderPipeline {
+pub fn build_lines(
+    device: &Device,
+    format: TextureFormat,
+    layout: &BindGroupLayout,
+    depth_format: TextureFormat,
+    depth_compare: wgpu::CompareFunction,
+) -> RenderPipeline {
+    lines(device, format, layout, "grid pipeline", depth_format, depth_compare)
+}
+
+/// Line pipeline drawn on top of the scene, ignoring the depth buffer.
+pub fn build_overlay(
+    device: &Device,
+    format: TextureFormat,
+    layout: &BindGroupLayout,
+    depth_format: TextureFormat,
+) -> RenderPipeline {
+    lines(device, format, layout, "overlay pipeline", depth_format, wgpu::CompareFunction::Always)
+}
+
+fn lines(
+    device: &Device,
+    format: TextureFormat,
+    layout: &BindGroupLayout,
+    label: &str,
+    depth_format: TextureFormat,
+    depth_compare: wgpu::CompareFunction,
+) -> RenderPipeline {
     let shader = device.create_shader_module(wgpu::include_wgsl!("../shader.wgsl"));
     let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
-        label: Some("grid pipeline layout"),
+        label: Some(label),
         bind_group_layouts: &[layout],
         push_constant_ranges: &[],
     });
     device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
-        label: Some("grid pipeline"),
+        label: Some(label),
         layout: Some(&pipeline_layout),
         vertex: wgpu::VertexState {
             module: &shader,
@@ -81,9 +136,9 @@ pub fn build_lines(device: &Device, format: TextureFormat, layout: &BindGroupLay
             ..Default::default()
         },
         depth_stencil: Some(wgpu::DepthStencilState {
-            format: wgpu::TextureFormat::Depth32Float,
+            format: depth_format,
             depth_write_enabled: false,
-            depth_compare: wgpu::CompareFunction::Less,
+            depth_compare,
             stencil: Default::default(),
             bias: Default::default(),
         }),
@@ -93,3 +148,59 @@ pub fn build_lines(device: &Device, format: TextureFormat, layout: &BindGroupLay
     })
 }
 
+/// Draws an enlarged silhouette wherever the stencil differs from the
+/// reference of the highlighted object.
+pub fn build_outline(
+    device: &Device,
+    format: TextureFormat,
+    layout: &BindGroupLayout,
+    depth_format: TextureFormat,
+) -> RenderPipeline {
+    let shader = device.create_shader_module(wgpu::include_wgsl!("../outline.wgsl"));
+    let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+        label: Some("outline pipeline layout"),
+        bind_group_layouts: &[layout],
+        push_constant_ranges: &[],
+    });
+    device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+        label: Some("outline pipeline"),
+        layout: Some(&pipeline_layout),
+        vertex: wgpu::VertexState {
+            module: &shader,
+            entry_point: Some("vs_outline"),
+            compilation_options: wgpu::PipelineCompilationOptions::default(),
+            buffers: &[Vertex::layout()],
+        },
+        fragment: Some(wgpu::FragmentState {
+            module: &shader,
+            entry_point: Some("fs_outline"),
+            compilation_options: wgpu::PipelineCompilationOptions::default(),
+            targets: &[Some(wgpu::ColorTargetState {
+                format,
+                blend: Some(wgpu::BlendState::ALPHA_BLENDING),
+                write_mask: wgpu::ColorWrites::ALL,
+            })],
+        }),
+        primitive: wgpu::PrimitiveState {
+            topology: wgpu::PrimitiveTopology::TriangleList,
+            cull_mode: None,
+            front_face: wgpu::FrontFace::Ccw,
+            ..Default::default()
+        },
+        depth_stencil: Some(wgpu::DepthStencilState {
+            format: depth_format,
+            depth_write_enabled: false,
+            depth_compare: wgpu::CompareFunction::Always,
+            stencil: wgpu::StencilState {
+                front: STENCIL_OUTSIDE,
+                back: STENCIL_OUTSIDE,
+                read_mask: 0xff,
+                write_mask: 0,
+            },
+            bias: Default::default(),
+        }),
+        multisample: wgpu::MultisampleState::default(),
+        multiview: None,
+        cache: None,
+    })
+}
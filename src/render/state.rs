@@ -1,12 +1,181 @@
 #![cfg(target_arch = "wasm32")]
 
 use glam::Mat4;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
 use wasm_bindgen::JsValue;
-use web_sys::HtmlCanvasElement;
 use wgpu::util::DeviceExt;
 
-use crate::render::data::{self, SceneUniforms, Light};
+use crate::input::view_cube;
+use crate::render::data::{self, Light, OutlineUniforms, SceneUniforms};
+use crate::render::gpu::{self, Canvas, Gpu};
 use crate::render::{depth, pipeline};
+use crate::scene::bounds::Aabb;
+use crate::scene::world::WorldFrame;
+
+/// Capacity of the transform gizmo line buffer.
+const GIZMO_MAX_VERTICES: usize = 1024;
+/// Capacity of the camera path debug line buffer.
+const PATH_MAX_VERTICES: usize = 8192;
+/// Lights in the Y-up display frame, see `WorldFrame`.
+const LIGHTS: [Light; 2] = [
+    Light {
+        position: [1.5, 1.0, 2.0],
+        _pad_p: 0.0,
+        color: [1.0, 1.0, 1.0],
+        _pad_c: 0.0,
+    },
+    Light {
+        position: [-1.5, 1.0, -2.0],
+        _pad_p: 0.0,
+        color: [1.0, 0.0, 0.0],
+        _pad_c: 0.0,
+    },
+];
+/// Half extent of the ground grid.
+const GRID_SIZE: i32 = 10;
+
+/// How an object is emphasised by the outline pass.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Highlight {
+    None,
+    Hovered,
+    Selected,
+}
+
+/// An object to draw. Every object uses the cube mesh for now.
+#[derive(Copy, Clone, Debug)]
+pub struct DrawObject {
+    pub model: Mat4,
+    pub highlight: Highlight,
+}
+
+/// Stencil value the object at `index` tags its pixels with, so its outline
+/// leaves out its own silhouette but not that of objects in front. Distinct
+/// for the first 255 objects; 0 is the background.
+fn stencil_reference(index: usize) -> u32 {
+    (index % 255) as u32 + 1
+}
+
+/// Colours (RGBA) and width in pixels of the selection silhouette.
+#[derive(Copy, Clone, Debug)]
+pub struct OutlineStyle {
+    pub selected: [f32; 4],
+    pub hovered: [f32; 4],
+    pub width: f32,
+}
+
+fn create_uniform_buffer(device: &wgpu::Device, label: &str, size: usize) -> wgpu::Buffer {
+    device.create_buffer(&wgpu::BufferDescriptor {
+        label: Some(label),
+        size: size as wgpu::BufferAddress,
+        usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+        mapped_at_creation: false,
+    })
+}
+
+fn create_bind_group(
+    device: &wgpu::Device,
+    label: &str,
+    layout: &wgpu::BindGroupLayout,
+    buffer: &wgpu::Buffer,
+) -> wgpu::BindGroup {
+    device.create_bind_group(&wgpu::BindGroupDescriptor {
+        layout,
+        entries: &[wgpu::BindGroupEntry {
+            binding: 0,
+            resource: buffer.as_entire_binding(),
+        }],
+        label: Some(label),
+    })
+}
+
+/// Uniforms of one object in one viewport.
+struct ObjectBindings {
+    uniform_buffer: wgpu::Buffer,
+    bind_group: wgpu::BindGroup,
+    outline_uniform_buffer: wgpu::Buffer,
+    outline_bind_group: wgpu::BindGroup,
+}
+
+impl ObjectBindings {
+    fn new(
+        device: &wgpu::Device,
+        layout: &wgpu::BindGroupLayout,
+        outline_layout: &wgpu::BindGroupLayout,
+    ) -> Self {
+        let (scene_size, outline_size) =
+            (std::mem::size_of::<SceneUniforms>(), std::mem::size_of::<OutlineUniforms>());
+        let uniform_buffer = create_uniform_buffer(device, "object uniform buffer", scene_size);
+        let outline_uniform_buffer =
+            create_uniform_buffer(device, "outline uniform buffer", outline_size);
+        Self {
+            bind_group: create_bind_group(device, "object bind group", layout, &uniform_buffer),
+            outline_bind_group: create_bind_group(
+                device,
+                "outline bind group",
+                outline_layout,
+                &outline_uniform_buffer,
+            ),
+            uniform_buffer,
+            outline_uniform_buffer,
+        }
+    }
+
+    fn destroy(&self) {
+        self.uniform_buffer.destroy();
+        self.outline_uniform_buffer.destroy();
+    }
+}
+
+/// Uniforms of one viewport. The geometry is shared, each viewport draws it
+/// with its own camera.
+struct View {
+    /// `[x, y, width, height]` as fractions of the canvas, from the top left.
+    rect: [f32; 4],
+    /// One per drawn object, grown as objects are added.
+    objects: Vec<ObjectBindings>,
+    grid_uniform_buffer: wgpu::Buffer,
+    grid_bind_group: wgpu::BindGroup,
+    view_cube_uniform_buffer: wgpu::Buffer,
+    view_cube_bind_group: wgpu::BindGroup,
+}
+
+impl View {
+    fn new(device: &wgpu::Device, layout: &wgpu::BindGroupLayout, rect: [f32; 4]) -> Self {
+        let scene_size = std::mem::size_of::<SceneUniforms>();
+        let grid_uniform_buffer = create_uniform_buffer(device, "grid uniform buffer", scene_size);
+        let view_cube_uniform_buffer =
+            create_uniform_buffer(device, "view cube uniform buffer", scene_size);
+        Self {
+            rect,
+            objects: Vec::new(),
+            grid_bind_group: create_bind_group(device, "grid bind group", layout, &grid_uniform_buffer),
+            view_cube_bind_group: create_bind_group(
+                device,
+                "view cube bind group",
+                layout,
+                &view_cube_uniform_buffer,
+            ),
+            grid_uniform_buffer,
+            view_cube_uniform_buffer,
+        }
+    }
+
+    fn destroy(&self) {
+        for object in &self.objects {
+            object.destroy();
+        }
+        self.grid_uniform_buffer.destroy();
+        self.view_cube_uniform_buffer.destroy();
+    }
+
+    /// Rectangle in pixels of a `width` x `height` target.
+    fn pixels(&self, width: f32, height: f32) -> [f32; 4] {
+        let [x, y, w, h] = self.rect;
+        [x * width, y * height, w * width, h * height]
+    }
+}
 
 pub struct State {
     grid_pipeline: wgpu::RenderPipeline,
@@ -14,18 +183,34 @@ pub struct State {
     grid_vertex_count: u32,
     light_vertex_buffer: wgpu::Buffer,
     light_vertex_count: u32,
+    overlay_pipeline: wgpu::RenderPipeline,
+    view_cube_vertex_buffer: wgpu::Buffer,
+    view_cube_vertex_count: u32,
+    gizmo_vertex_buffer: wgpu::Buffer,
+    gizmo_vertex_count: u32,
+    path_vertex_buffer: wgpu::Buffer,
+    path_vertex_count: u32,
+    outline_pipeline: wgpu::RenderPipeline,
+    /// Objects drawn in every viewport, see `set_objects`.
+    objects: Vec<DrawObject>,
+    pub outline: OutlineStyle,
     pub draw_grid: bool,
+    reverse_z: bool,
+    front_face: wgpu::FrontFace,
+    /// Display to world frame, applied to the grid and lights.
+    world: Mat4,
+    lights: [Light; 2],
+    bind_group_layout: wgpu::BindGroupLayout,
+    outline_bind_group_layout: wgpu::BindGroupLayout,
+    views: Vec<View>,
     surface: wgpu::Surface<'static>,
     device: wgpu::Device,
+    device_lost: Arc<AtomicBool>,
     queue: wgpu::Queue,
     config: wgpu::SurfaceConfiguration,
     pipeline: wgpu::RenderPipeline,
     vertex_buffer: wgpu::Buffer,
     index_buffer: wgpu::Buffer,
-    cube_uniform_buffer: wgpu::Buffer,
-    grid_uniform_buffer: wgpu::Buffer,
-    cube_bind_group: wgpu::BindGroup,
-    grid_bind_group: wgpu::BindGroup,
     depth_texture: wgpu::Texture,
     depth_view: wgpu::TextureView,
     depth_format: wgpu::TextureFormat,
@@ -33,39 +218,17 @@ pub struct State {
 }
 
 impl State {
-    pub async fn new(canvas: &HtmlCanvasElement) -> Result<Self, JsValue> {
-        let instance = wgpu::Instance::default();
-        let surface = instance
-            .create_surface(wgpu::SurfaceTarget::Canvas(canvas.clone()))
-            .map_err(|e| JsValue::from_str(&format!("{e:?}")))?;
-        let surface =
-            unsafe { std::mem::transmute::<wgpu::Surface<'_>, wgpu::Surface<'static>>(surface) };
-        let adapter = instance
-            .request_adapter(&wgpu::RequestAdapterOptions {
-                power_preference: wgpu::PowerPreference::HighPerformance,
-                compatible_surface: Some(&surface),
-                force_fallback_adapter: false,
-            })
-            .await
-            .map_err(|e| JsValue::from_str(&e.to_string()))?;
-        let (device, queue) = adapter
-            .request_device(
-                &wgpu::DeviceDescriptor {
-                    label: None,
-                    required_features: wgpu::Features::empty(),
-                    required_limits: adapter.limits(),
-                    memory_hints: wgpu::MemoryHints::default(),
-                    trace: wgpu::Trace::default(),
-                },
-            )
-            .await
-            .map_err(|e| JsValue::from_str(&e.to_string()))?;
+    /// Sets up rendering into `canvas` on the device shared by all viewers.
+    pub async fn new(canvas: &Canvas) -> Result<Self, JsValue> {
+        let (gpu, surface) = Gpu::surface(canvas).await?;
+        let (width, height) = canvas.size();
+        let Gpu { adapter, device, queue, lost: device_lost, .. } = gpu;
         let caps = surface.get_capabilities(&adapter);
         let config = wgpu::SurfaceConfiguration {
             usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
             format: caps.formats[0],
-            width: canvas.width(),
-            height: canvas.height(),
+            width,
+            height,
             present_mode: caps.present_modes[0],
             desired_maximum_frame_latency: 2,
             alpha_mode: caps.alpha_modes[0],
@@ -74,7 +237,7 @@ impl State {
         surface.configure(&device, &config);
         let aspect = config.width as f32 / config.height as f32;
 
-        let depth_format = wgpu::TextureFormat::Depth32Float;
+        let depth_format = depth::format(&device, false);
         let (depth_texture, depth_view) =
             depth::create(&device, config.width, config.height, depth_format);
 
@@ -103,80 +266,81 @@ impl State {
             }],
         });
 
-        let pipeline = pipeline::build(&device, config.format, &bind_group_layout);
-        let grid_pipeline = pipeline::build_lines(&device, config.format, &bind_group_layout);
-        let grid_vertices = data::grid_vertices(10);
+        let depth_compare = depth::compare(false);
+        let front_face = wgpu::FrontFace::Ccw;
+        let pipeline = pipeline::build(
+            &device,
+            config.format,
+            &bind_group_layout,
+            depth_format,
+            depth_compare,
+            front_face,
+        );
+        let grid_pipeline = pipeline::build_lines(
+            &device,
+            config.format,
+            &bind_group_layout,
+            depth_format,
+            depth_compare,
+        );
+        let grid_vertices = data::grid_vertices(GRID_SIZE);
         let grid_vertex_count = grid_vertices.len() as u32;
         let grid_vertex_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
             label: Some("grid vertex buffer"),
             contents: data::as_bytes(&grid_vertices),
-            usage: wgpu::BufferUsages::VERTEX,
+            usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
         });
 
-        let uniform = SceneUniforms {
-            mvp: [
-                [1.0, 0.0, 0.0, 0.0],
-                [0.0, 1.0, 0.0, 0.0],
-                [0.0, 0.0, 1.0, 0.0],
-                [0.0, 0.0, 0.0, 1.0],
-            ],
-            model: [
-                [1.0, 0.0, 0.0, 0.0],
-                [0.0, 1.0, 0.0, 0.0],
-                [0.0, 0.0, 1.0, 0.0],
-                [0.0, 0.0, 0.0, 1.0],
-            ],
-            camera_pos: [0.0, 0.0, 0.0],
-            _pad0: 0.0,
-            lights: [
-                Light {
-                    position: [1.5, 1.0, 2.0],
-                    _pad_p: 0.0,
-                    color: [1.0, 1.0, 1.0],
-                    _pad_c: 0.0,
-                },
-                Light {
-                    position: [-1.5, 1.0, -2.0],
-                    _pad_p: 0.0,
-                    color: [1.0, 0.0, 0.0],
-                    _pad_c: 0.0,
-                },
-            ],
-        };
-
-        let light_vertices = data::light_rays(&uniform.lights);
+        let light_vertices = data::light_rays(&LIGHTS);
         let light_vertex_count = light_vertices.len() as u32;
         let light_vertex_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
             label: Some("light vertex buffer"),
             contents: data::as_bytes(&light_vertices),
             usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
         });
-        let cube_uniform_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
-            label: Some("cube uniform buffer"),
-            contents: data::as_bytes(&[uniform]),
-            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+
+        let overlay_pipeline =
+            pipeline::build_overlay(&device, config.format, &bind_group_layout, depth_format);
+        let view_cube_vertices = data::axis_triad();
+        let view_cube_vertex_count = view_cube_vertices.len() as u32;
+        let view_cube_vertex_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("view cube vertex buffer"),
+            contents: data::as_bytes(&view_cube_vertices),
+            usage: wgpu::BufferUsages::VERTEX,
         });
-        let cube_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
-            layout: &bind_group_layout,
-            entries: &[wgpu::BindGroupEntry {
-                binding: 0,
-                resource: cube_uniform_buffer.as_entire_binding(),
-            }],
-            label: Some("bind group"),
+        let gizmo_vertex_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("gizmo vertex buffer"),
+            size: (GIZMO_MAX_VERTICES * std::mem::size_of::<data::Vertex>()) as wgpu::BufferAddress,
+            usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
         });
-        let grid_uniform_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
-            label: Some("grid uniform buffer"),
-            contents: data::as_bytes(&[uniform]),
-            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+        let path_vertex_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("camera path vertex buffer"),
+            size: (PATH_MAX_VERTICES * std::mem::size_of::<data::Vertex>()) as wgpu::BufferAddress,
+            usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
         });
-        let grid_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
-            layout: &bind_group_layout,
-            entries: &[wgpu::BindGroupEntry {
+
+        let outline_bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("outline bind group layout"),
+            entries: &[wgpu::BindGroupLayoutEntry {
                 binding: 0,
-                resource: grid_uniform_buffer.as_entire_binding(),
+                visibility: wgpu::ShaderStages::VERTEX_FRAGMENT,
+                ty: wgpu::BindingType::Buffer {
+                    ty: wgpu::BufferBindingType::Uniform,
+                    has_dynamic_offset: false,
+                    min_binding_size: None,
+                },
+                count: None,
             }],
-            label: Some("grid bind group"),
         });
+        let outline_pipeline = pipeline::build_outline(
+            &device,
+            config.format,
+            &outline_bind_group_layout,
+            depth_format,
+        );
+        let views = vec![View::new(&device, &bind_group_layout, [0.0, 0.0, 1.0, 1.0])];
 
         Ok(Self {
             grid_pipeline,
@@ -184,18 +348,36 @@ impl State {
             grid_vertex_count,
             light_vertex_buffer,
             light_vertex_count,
+            overlay_pipeline,
+            view_cube_vertex_buffer,
+            view_cube_vertex_count,
+            gizmo_vertex_buffer,
+            gizmo_vertex_count: 0,
+            path_vertex_buffer,
+            path_vertex_count: 0,
+            outline_pipeline,
+            objects: Vec::new(),
+            outline: OutlineStyle {
+                selected: [1.0, 0.6, 0.1, 1.0],
+                hovered: [1.0, 1.0, 1.0, 0.6],
+                width: 3.0,
+            },
             draw_grid: true,
+            reverse_z: false,
+            front_face,
+            world: Mat4::IDENTITY,
+            lights: LIGHTS,
+            bind_group_layout,
+            outline_bind_group_layout,
+            views,
             surface,
             device,
+            device_lost,
             queue,
             config,
             pipeline,
             vertex_buffer,
             index_buffer,
-            cube_uniform_buffer,
-            grid_uniform_buffer,
-            cube_bind_group,
-            grid_bind_group,
             depth_texture,
             depth_view,
             depth_format,
@@ -206,6 +388,143 @@ impl State {
         self.draw_grid = show;
     }
 
+    /// Switches the depth test between standard and reversed depth. Must
+    /// match `Lens::reverse_z` of the camera being rendered. Reversed depth
+    /// moves to a float depth buffer where the device supports one.
+    pub fn set_reverse_z(&mut self, reverse_z: bool) {
+        if reverse_z == self.reverse_z {
+            return;
+        }
+        self.reverse_z = reverse_z;
+        let depth_format = depth::format(&self.device, reverse_z);
+        if depth_format != self.depth_format {
+            self.depth_format = depth_format;
+            self.depth_texture.destroy();
+            (self.depth_texture, self.depth_view) =
+                depth::create(&self.device, self.config.width, self.config.height, depth_format);
+            self.overlay_pipeline = pipeline::build_overlay(
+                &self.device,
+                self.config.format,
+                &self.bind_group_layout,
+                depth_format,
+            );
+            self.outline_pipeline = pipeline::build_outline(
+                &self.device,
+                self.config.format,
+                &self.outline_bind_group_layout,
+                depth_format,
+            );
+        }
+        let compare = depth::compare(reverse_z);
+        self.pipeline = pipeline::build(
+            &self.device,
+            self.config.format,
+            &self.bind_group_layout,
+            depth_format,
+            compare,
+            self.front_face,
+        );
+        self.grid_pipeline = pipeline::build_lines(
+            &self.device,
+            self.config.format,
+            &self.bind_group_layout,
+            depth_format,
+            compare,
+        );
+    }
+
+    /// Lays the grid and lights out for `frame`, so the grid lies in the
+    /// world's ground plane.
+    pub fn set_world_frame(&mut self, frame: WorldFrame) {
+        self.set_world(frame.from_display());
+    }
+
+    fn set_world(&mut self, world: Mat4) {
+        self.world = world;
+        self.lights = LIGHTS.map(|l| Light {
+            position: self.world.transform_point3(l.position.into()).into(),
+            ..l
+        });
+        let grid_vertices: Vec<data::Vertex> = data::grid_vertices(GRID_SIZE)
+            .into_iter()
+            .map(|v| data::Vertex {
+                position: self.world.transform_point3(v.position.into()).into(),
+                normal: self.world.transform_vector3(v.normal.into()).into(),
+                ..v
+            })
+            .collect();
+        self.queue
+            .write_buffer(&self.grid_vertex_buffer, 0, data::as_bytes(&grid_vertices));
+    }
+
+    /// Flips which triangle winding counts as front facing, for models whose
+    /// transform to the screen includes a mirror.
+    pub fn set_mirrored(&mut self, mirrored: bool) {
+        let front_face = if mirrored {
+            wgpu::FrontFace::Cw
+        } else {
+            wgpu::FrontFace::Ccw
+        };
+        if front_face == self.front_face {
+            return;
+        }
+        self.front_face = front_face;
+        self.pipeline = pipeline::build(
+            &self.device,
+            self.config.format,
+            &self.bind_group_layout,
+            self.depth_format,
+            depth::compare(self.reverse_z),
+            front_face,
+        );
+    }
+
+    /// Bounds of the helper geometry (grid and light markers) while shown.
+    pub fn helper_bounds(&self) -> Option<Aabb> {
+        if !self.draw_grid {
+            return None;
+        }
+        let size = GRID_SIZE as f32;
+        let grid = Aabb::new(glam::Vec3::new(-size, 0.0, -size), glam::Vec3::new(size, 0.0, size))
+            .transformed(self.world);
+        let lights = Aabb::from_points(self.lights.iter().map(|l| glam::Vec3::from(l.position)));
+        Some(lights.map_or(grid, |l| grid.union(&l)))
+    }
+
+    /// Whether the device was lost and this renderer has to be replaced,
+    /// see `restore`.
+    pub fn is_device_lost(&self) -> bool {
+        self.device_lost.load(Ordering::Relaxed)
+    }
+
+    /// Gives up the device as if it was lost, e.g. after running out of
+    /// memory, so every renderer on it is replaced by one on a new device.
+    pub fn lose_device(&self) {
+        gpu::set_lost(&self.device_lost);
+    }
+
+    /// Takes over the settings of `old`, a renderer whose device was lost.
+    /// Per-frame data comes with the next `update`.
+    pub fn restore(&mut self, old: &State) {
+        self.set_objects(old.objects.clone());
+        self.outline = old.outline;
+        self.draw_grid = old.draw_grid;
+        self.set_reverse_z(old.reverse_z);
+        self.set_world(old.world);
+        self.set_viewports(&old.views.iter().map(|v| v.rect).collect::<Vec<_>>());
+        self.resize(old.config.width, old.config.height);
+    }
+
+    /// Largest width or height of the drawing buffer the device supports.
+    pub fn max_size(&self) -> u32 {
+        self.device.limits().max_texture_dimension_2d
+    }
+
+    /// Configures the surface again after it was lost or went out of date.
+    pub fn reconfigure(&self) {
+        self.surface.configure(&self.device, &self.config);
+    }
+
     pub fn resize(&mut self, width: u32, height: u32) {
         if width == 0 || height == 0 {
             return;
@@ -221,29 +540,70 @@ impl State {
     }
 
 
+    /// Splits the canvas into viewports, each `[x, y, width, height]` as
+    /// fractions of the canvas measured from the top-left corner. Every
+    /// viewport is fed by `update` and `update_view_cube` with its index.
+    pub fn set_viewports(&mut self, rects: &[[f32; 4]]) {
+        self.views.truncate(rects.len());
+        for (i, rect) in rects.iter().enumerate() {
+            match self.views.get_mut(i) {
+                Some(view) => view.rect = *rect,
+                None => self.views.push(View::new(&self.device, &self.bind_group_layout, *rect)),
+            }
+        }
+        self.reserve_objects();
+    }
+
+    /// Replaces the objects drawn in every viewport. Their uniforms come
+    /// with the next `update`.
+    pub fn set_objects(&mut self, objects: Vec<DrawObject>) {
+        self.objects = objects;
+        self.reserve_objects();
+    }
+
+    /// Gives every viewport uniforms for each object.
+    fn reserve_objects(&mut self) {
+        for view in &mut self.views {
+            while view.objects.len() < self.objects.len() {
+                view.objects.push(ObjectBindings::new(
+                    &self.device,
+                    &self.bind_group_layout,
+                    &self.outline_bind_group_layout,
+                ));
+            }
+        }
+    }
 
-    pub fn update(&self, camera_matrix: Mat4, model: Mat4, camera_pos: glam::Vec3) {
-        let cube_mvp = camera_matrix * model;
-        let cube_uniform = SceneUniforms {
-            mvp: cube_mvp.to_cols_array_2d(),
-            model: model.to_cols_array_2d(),
-            camera_pos: camera_pos.into(),
-            _pad0: 0.0,
-            lights: [
-                Light {
-                    position: [1.5, 1.0, 2.0],
-                    _pad_p: 0.0,
-                    color: [1.0, 1.0, 1.0],
-                    _pad_c: 0.0,
-                },
-                Light {
-                    position: [-1.5, 1.0, -2.0],
-                    _pad_p: 0.0,
-                    color: [1.0, 0.0, 0.0],
-                    _pad_c: 0.0,
+    pub fn update(&self, index: usize, camera_matrix: Mat4, camera_pos: glam::Vec3) {
+        let view = &self.views[index];
+        let [_, _, w, h] = view.pixels(self.config.width as f32, self.config.height as f32);
+        for (object, bindings) in self.objects.iter().zip(&view.objects) {
+            let mvp = camera_matrix * object.model;
+            let uniform = SceneUniforms {
+                mvp: mvp.to_cols_array_2d(),
+                model: object.model.to_cols_array_2d(),
+                camera_pos: camera_pos.into(),
+                _pad0: 0.0,
+                lights: self.lights,
+            };
+            self.queue
+                .write_buffer(&bindings.uniform_buffer, 0, data::as_bytes(&[uniform]));
+            if object.highlight == Highlight::None {
+                continue;
+            }
+            let outline_uniform = OutlineUniforms {
+                mvp: mvp.to_cols_array_2d(),
+                color: match object.highlight {
+                    Highlight::Selected => self.outline.selected,
+                    _ => self.outline.hovered,
                 },
-            ],
-        };
+                viewport: [w, h],
+                width: self.outline.width,
+                _pad: 0.0,
+            };
+            self.queue
+                .write_buffer(&bindings.outline_uniform_buffer, 0, data::as_bytes(&[outline_uniform]));
+        }
         let grid_uniform = SceneUniforms {
             mvp: camera_matrix.to_cols_array_2d(),
             model: [
@@ -254,30 +614,75 @@ impl State {
             ],
             camera_pos: camera_pos.into(),
             _pad0: 0.0,
-            lights: [
-                Light {
-                    position: [1.5, 1.0, 2.0],
-                    _pad_p: 0.0,
-                    color: [1.0, 1.0, 1.0],
-                    _pad_c: 0.0,
-                },
-                Light {
-                    position: [-1.5, 1.0, -2.0],
-                    _pad_p: 0.0,
-                    color: [1.0, 0.0, 0.0],
-                    _pad_c: 0.0,
-                },
-            ],
+            lights: self.lights,
         };
         self.queue
-            .write_buffer(&self.cube_uniform_buffer, 0, data::as_bytes(&[cube_uniform]));
-        self.queue
-            .write_buffer(&self.grid_uniform_buffer, 0, data::as_bytes(&[grid_uniform]));
-        let light_vertices = data::light_rays(&cube_uniform.lights);
+            .write_buffer(&view.grid_uniform_buffer, 0, data::as_bytes(&[grid_uniform]));
+        let light_vertices = data::light_rays(&self.lights);
         self.queue
             .write_buffer(&self.light_vertex_buffer, 0, data::as_bytes(&light_vertices));
     }
 
+    /// Uploads the orientation of the corner axis triad of viewport `index`,
+    /// see `view_cube::matrix`.
+    pub fn update_view_cube(&self, index: usize, matrix: Mat4) {
+        let uniform = SceneUniforms {
+            mvp: matrix.to_cols_array_2d(),
+            model: Mat4::IDENTITY.to_cols_array_2d(),
+            camera_pos: [0.0; 3],
+            _pad0: 0.0,
+            lights: [Light {
+                position: [0.0; 3],
+                _pad_p: 0.0,
+                color: [0.0; 3],
+                _pad_c: 0.0,
+            }; 2],
+        };
+        self.queue
+            .write_buffer(&self.views[index].view_cube_uniform_buffer, 0, data::as_bytes(&[uniform]));
+    }
+
+    /// Replaces the transform gizmo lines, given in world space.
+    pub fn set_gizmo_vertices(&mut self, vertices: &[data::Vertex]) {
+        let vertices = &vertices[..vertices.len().min(GIZMO_MAX_VERTICES)];
+        self.gizmo_vertex_count = vertices.len() as u32;
+        if !vertices.is_empty() {
+            self.queue
+                .write_buffer(&self.gizmo_vertex_buffer, 0, data::as_bytes(vertices));
+        }
+    }
+
+    /// Replaces the camera path debug lines, given in world space.
+    pub fn set_path_vertices(&mut self, vertices: &[data::Vertex]) {
+        let vertices = &vertices[..vertices.len().min(PATH_MAX_VERTICES)];
+        self.path_vertex_count = vertices.len() as u32;
+        if !vertices.is_empty() {
+            self.queue
+                .write_buffer(&self.path_vertex_buffer, 0, data::as_bytes(vertices));
+        }
+    }
+
+    /// Frees the GPU memory of this renderer right away instead of when the
+    /// device is dropped, which with a shared device may be never. Nothing
+    /// may be rendered afterwards.
+    pub fn destroy(&mut self) {
+        for view in self.views.drain(..) {
+            view.destroy();
+        }
+        for buffer in [
+            &self.vertex_buffer,
+            &self.index_buffer,
+            &self.grid_vertex_buffer,
+            &self.light_vertex_buffer,
+            &self.view_cube_vertex_buffer,
+            &self.gizmo_vertex_buffer,
+            &self.path_vertex_buffer,
+        ] {
+            buffer.destroy();
+        }
+        self.depth_texture.destroy();
+    }
+
     pub fn render(&mut self) -> Result<(), wgpu::SurfaceError> {
         let frame = self.surface.get_current_texture()?;
         let view = frame
@@ -307,26 +712,80 @@ impl State {
                 depth_stencil_attachment: Some(wgpu::RenderPassDepthStencilAttachment {
                     view: &self.depth_view,
                     depth_ops: Some(wgpu::Operations {
-                        load: wgpu::LoadOp::Clear(1.0),
+                        load: wgpu::LoadOp::Clear(depth::clear_value(self.reverse_z)),
+                        store: wgpu::StoreOp::Store,
+                    }),
+                    stencil_ops: Some(wgpu::Operations {
+                        load: wgpu::LoadOp::Clear(0),
                         store: wgpu::StoreOp::Store,
                     }),
-                    stencil_ops: None,
                 }),
                 occlusion_query_set: None,
                 timestamp_writes: None,
             });
-            rp.set_pipeline(&self.pipeline);
-            rp.set_bind_group(0, &self.cube_bind_group, &[]);
-            rp.set_vertex_buffer(0, self.vertex_buffer.slice(..));
-            rp.set_index_buffer(self.index_buffer.slice(..), wgpu::IndexFormat::Uint16);
-            rp.draw_indexed(0..data::INDICES.len() as u32, 0, 0..1);
-            if self.draw_grid {
-                rp.set_pipeline(&self.grid_pipeline);
-                rp.set_bind_group(0, &self.grid_bind_group, &[]);
-                rp.set_vertex_buffer(0, self.grid_vertex_buffer.slice(..));
-                rp.draw(0..self.grid_vertex_count, 0..1);
-                rp.set_vertex_buffer(0, self.light_vertex_buffer.slice(..));
-                rp.draw(0..self.light_vertex_count, 0..1);
+            let (width, height) = (self.config.width as f32, self.config.height as f32);
+            for view in &self.views {
+                let [vx, vy, vw, vh] = view.pixels(width, height);
+                if vw < 1.0 || vh < 1.0 {
+                    continue;
+                }
+                rp.set_viewport(vx, vy, vw, vh, 0.0, 1.0);
+                // Keeps the outline of one viewport from spilling into the next.
+                let (sx, sy) = (vx as u32, vy as u32);
+                rp.set_scissor_rect(
+                    sx,
+                    sy,
+                    (vw as u32).min(self.config.width - sx),
+                    (vh as u32).min(self.config.height - sy),
+                );
+                rp.set_pipeline(&self.pipeline);
+                rp.set_vertex_buffer(0, self.vertex_buffer.slice(..));
+                rp.set_index_buffer(self.index_buffer.slice(..), wgpu::IndexFormat::Uint16);
+                for (i, bindings) in view.objects.iter().take(self.objects.len()).enumerate() {
+                    rp.set_stencil_reference(stencil_reference(i));
+                    rp.set_bind_group(0, &bindings.bind_group, &[]);
+                    rp.draw_indexed(0..data::INDICES.len() as u32, 0, 0..1);
+                }
+                if self.draw_grid {
+                    rp.set_pipeline(&self.grid_pipeline);
+                    rp.set_bind_group(0, &view.grid_bind_group, &[]);
+                    rp.set_vertex_buffer(0, self.grid_vertex_buffer.slice(..));
+                    rp.draw(0..self.grid_vertex_count, 0..1);
+                    rp.set_vertex_buffer(0, self.light_vertex_buffer.slice(..));
+                    rp.draw(0..self.light_vertex_count, 0..1);
+                }
+                if self.path_vertex_count > 0 {
+                    rp.set_pipeline(&self.grid_pipeline);
+                    rp.set_bind_group(0, &view.grid_bind_group, &[]);
+                    rp.set_vertex_buffer(0, self.path_vertex_buffer.slice(..));
+                    rp.draw(0..self.path_vertex_count, 0..1);
+                }
+                let highlighted = self
+                    .objects
+                    .iter()
+                    .zip(&view.objects)
+                    .enumerate()
+                    .filter(|(_, (object, _))| object.highlight != Highlight::None);
+                for (i, (_, bindings)) in highlighted {
+                    rp.set_pipeline(&self.outline_pipeline);
+                    rp.set_stencil_reference(stencil_reference(i));
+                    rp.set_bind_group(0, &bindings.outline_bind_group, &[]);
+                    rp.set_vertex_buffer(0, self.vertex_buffer.slice(..));
+                    rp.set_index_buffer(self.index_buffer.slice(..), wgpu::IndexFormat::Uint16);
+                    rp.draw_indexed(0..data::INDICES.len() as u32, 0, 0..1);
+                }
+                if self.gizmo_vertex_count > 0 {
+                    rp.set_pipeline(&self.overlay_pipeline);
+                    rp.set_bind_group(0, &view.grid_bind_group, &[]);
+                    rp.set_vertex_buffer(0, self.gizmo_vertex_buffer.slice(..));
+                    rp.draw(0..self.gizmo_vertex_count, 0..1);
+                }
+                let [x, y, w, h] = view_cube::rect(vw, vh);
+                rp.set_viewport(vx + x, vy + y, w, h, 0.0, 1.0);
+                rp.set_pipeline(&self.overlay_pipeline);
+                rp.set_bind_group(0, &view.view_cube_bind_group, &[]);
+                rp.set_vertex_buffer(0, self.view_cube_vertex_buffer.slice(..));
+                rp.draw(0..self.view_cube_vertex_count, 0..1);
             }
         }
         self.queue.submit(Some(encoder.finish()));
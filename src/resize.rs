@@ -0,0 +1,139 @@
+#![cfg(target_arch = "wasm32")]
+
+use std::cell::{Cell, RefCell};
+use std::rc::Rc;
+
+use js_sys::{Array, Reflect};
+use wasm_bindgen::{closure::Closure, JsCast, JsValue};
+use web_sys::{
+    Event, HtmlCanvasElement, ResizeObserver, ResizeObserverBoxOptions, ResizeObserverEntry,
+    ResizeObserverOptions, ResizeObserverSize,
+};
+
+use crate::input::listener::Listener;
+
+/// Watches the size of a canvas on the page; stops when dropped.
+pub struct SizeObserver {
+    observer: ResizeObserver,
+    _callback: Closure<dyn FnMut(Array)>,
+    /// Pixel ratio watch of the CSS size fallback, see `watch_ratio`.
+    ratio: Option<RatioWatch>,
+}
+
+/// The `change` listener of a media query matching the current pixel ratio.
+type RatioWatch = Rc<RefCell<Option<Listener>>>;
+
+/// Whether the browser reports sizes in device pixels. Safari does not, and
+/// rejects the option.
+fn has_device_pixel_box() -> bool {
+    let entry = Reflect::get(&js_sys::global(), &"ResizeObserverEntry".into()).unwrap_or_default();
+    let prototype = Reflect::get(&entry, &"prototype".into()).unwrap_or_default();
+    prototype.is_object()
+        && Reflect::has(&prototype, &"devicePixelContentBoxSize".into()).unwrap_or(false)
+}
+
+fn first_size(sizes: Array) -> Option<(f64, f64)> {
+    let size = sizes.get(0).dyn_into::<ResizeObserverSize>().ok()?;
+    Some((size.inline_size(), size.block_size()))
+}
+
+/// Calls `f` with the last observed CSS size scaled by the new pixel ratio
+/// whenever `devicePixelRatio` changes, for browsers whose resize observer
+/// cannot report device pixels. Moving the page to another screen or zooming
+/// changes the ratio without changing the CSS size, so the observer stays
+/// silent. A media query only matches one ratio, so each change arms a new one.
+fn watch_ratio(watch: &RatioWatch, css_size: Rc<Cell<Option<(f64, f64)>>>, f: Rc<RefCell<dyn FnMut(u32, u32)>>) {
+    let Some(window) = web_sys::window() else {
+        return;
+    };
+    let query = format!("(resolution: {}dppx)", window.device_pixel_ratio());
+    let Ok(Some(list)) = window.match_media(&query) else {
+        return;
+    };
+    let watch_c = watch.clone();
+    let listener = Listener::new(&list, "change", move |_: Event| {
+        let ratio = web_sys::window().map_or(1.0, |w| w.device_pixel_ratio());
+        if let Some((width, height)) = css_size.get() {
+            (f.borrow_mut())((width * ratio).round() as u32, (height * ratio).round() as u32);
+        }
+        watch_ratio(&watch_c, css_size.clone(), f.clone());
+    });
+    *watch.borrow_mut() = Some(listener);
+}
+
+/// Fixes the CSS size of a canvas laid out at its intrinsic size, one CSS
+/// pixel per buffer pixel. Such a canvas grows with its drawing buffer, so
+/// sizing the buffer from the observed size would resize it again on every
+/// callback: without end on a HiDPI screen, or down to one pixel with a
+/// render scale below 1. A canvas sized by CSS is taken to be one whose
+/// inline style sets the size or whose layout size differs from its buffer.
+fn pin_css_size(canvas: &HtmlCanvasElement) {
+    let style = canvas.style();
+    let axes = [
+        ("width", canvas.client_width(), canvas.width()),
+        ("height", canvas.client_height(), canvas.height()),
+    ];
+    for (property, css, buffer) in axes {
+        let unset = style.get_property_value(property).is_ok_and(|v| v.is_empty());
+        if unset && css > 0 && css as u32 == buffer {
+            let _ = style.set_property(property, &format!("{css}px"));
+        }
+    }
+}
+
+/// Calls `f` with the size of `canvas` in device pixels now and whenever it
+/// changes, including when the page moves to a screen with another pixel
+/// ratio. Exact device pixels keep the output sharp on HiDPI screens; where
+/// the browser cannot report them the CSS size is scaled by
+/// `devicePixelRatio`, and ratio changes are caught by `watch_ratio`. A canvas without a CSS size keeps its current one,
+/// see `pin_css_size`.
+pub fn observe(canvas: &HtmlCanvasElement, f: impl FnMut(u32, u32) + 'static) -> Result<SizeObserver, JsValue> {
+    let device_pixels = has_device_pixel_box();
+    let f: Rc<RefCell<dyn FnMut(u32, u32)>> = Rc::new(RefCell::new(f));
+    let css_size = Rc::new(Cell::new(None));
+    let ratio = (!device_pixels).then(|| {
+        let watch = RatioWatch::default();
+        watch_ratio(&watch, css_size.clone(), f.clone());
+        watch
+    });
+    let callback = Closure::wrap(Box::new(move |entries: Array| {
+        let Ok(entry) = entries.get(0).dyn_into::<ResizeObserverEntry>() else {
+            return;
+        };
+        let size = if device_pixels {
+            first_size(entry.device_pixel_content_box_size())
+        } else {
+            let ratio = web_sys::window().map_or(1.0, |w| w.device_pixel_ratio());
+            let size = first_size(entry.content_box_size());
+            css_size.set(size);
+            size.map(|(w, h)| (w * ratio, h * ratio))
+        };
+        if let Some((width, height)) = size {
+            (f.borrow_mut())(width.round() as u32, height.round() as u32);
+        }
+    }) as Box<dyn FnMut(Array)>);
+    let observer = ResizeObserver::new(callback.as_ref().unchecked_ref())?;
+    let options = ResizeObserverOptions::new();
+    options.set_box(if device_pixels {
+        ResizeObserverBoxOptions::DevicePixelContentBox
+    } else {
+        ResizeObserverBoxOptions::ContentBox
+    });
+    pin_css_size(canvas);
+    observer.observe_with_options(canvas, &options);
+    Ok(SizeObserver {
+        observer,
+        _callback: callback,
+        ratio,
+    })
+}
+
+impl Drop for SizeObserver {
+    fn drop(&mut self) {
+        self.observer.disconnect();
+        // The listener holds its own watch, so it has to be released here.
+        if let Some(watch) = &self.ratio {
+            watch.borrow_mut().take();
+        }
+    }
+}
@@ -0,0 +1,119 @@
+use glam::{Mat4, Vec2, Vec3};
+
+/// Axis-aligned bounding box.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Aabb {
+    pub min: Vec3,
+    pub max: Vec3,
+}
+
+impl Aabb {
+    pub fn new(min: Vec3, max: Vec3) -> Self {
+        Self { min, max }
+    }
+
+    pub fn center(&self) -> Vec3 {
+        (self.min + self.max) * 0.5
+    }
+
+    pub fn corners(&self) -> [Vec3; 8] {
+        let (a, b) = (self.min, self.max);
+        [
+            Vec3::new(a.x, a.y, a.z),
+            Vec3::new(b.x, a.y, a.z),
+            Vec3::new(a.x, b.y, a.z),
+            Vec3::new(b.x, b.y, a.z),
+            Vec3::new(a.x, a.y, b.z),
+            Vec3::new(b.x, a.y, b.z),
+            Vec3::new(a.x, b.y, b.z),
+            Vec3::new(b.x, b.y, b.z),
+        ]
+    }
+
+    pub fn from_points(points: impl IntoIterator<Item = Vec3>) -> Option<Aabb> {
+        points
+            .into_iter()
+            .map(|p| Aabb::new(p, p))
+            .reduce(|a, b| a.union(&b))
+    }
+
+    pub fn union(&self, other: &Aabb) -> Aabb {
+        Aabb::new(self.min.min(other.min), self.max.max(other.max))
+    }
+
+    pub fn intersects(&self, other: &Aabb) -> bool {
+        self.min.cmple(other.max).all() && other.min.cmple(self.max).all()
+    }
+
+    /// Box enclosing this box after transforming it by `m`.
+    pub fn transformed(&self, m: Mat4) -> Aabb {
+        Aabb::from_points(self.corners().map(|c| m.transform_point3(c))).unwrap()
+    }
+
+    /// Distance along `ray` to the first intersection, if any.
+    pub fn intersect(&self, ray: &Ray) -> Option<f32> {
+        let inv = ray.direction.recip();
+        let t0 = (self.min - ray.origin) * inv;
+        let t1 = (self.max - ray.origin) * inv;
+        let near = t0.min(t1).max_element();
+        let far = t0.max(t1).min_element();
+        (far >= near.max(0.0)).then_some(near.max(0.0))
+    }
+}
+
+#[derive(Copy, Clone, Debug)]
+pub struct Ray {
+    pub origin: Vec3,
+    pub direction: Vec3,
+}
+
+impl Ray {
+    pub fn new(origin: Vec3, direction: Vec3) -> Self {
+        Self {
+            origin,
+            direction: direction.normalize(),
+        }
+    }
+
+    /// Ray through the point `ndc` (normalized device coordinates, y up) of
+    /// the view described by the combined `view_proj` matrix. With
+    /// `reverse_z` the far plane may be at infinity, so the direction is
+    /// taken towards depth 0.5 instead.
+    pub fn from_ndc(view_proj: Mat4, ndc: Vec2, reverse_z: bool) -> Self {
+        let inv = view_proj.inverse();
+        let near_depth = if reverse_z { 1.0 } else { 0.0 };
+        let near = inv.project_point3(ndc.extend(near_depth));
+        let mid = inv.project_point3(ndc.extend(0.5));
+        Ray::new(near, mid - near)
+    }
+
+    pub fn at(&self, t: f32) -> Vec3 {
+        self.origin + self.direction * t
+    }
+
+    /// Distance along the ray to the plane through `point` with `normal`.
+    pub fn intersect_plane(&self, point: Vec3, normal: Vec3) -> Option<f32> {
+        let denom = self.direction.dot(normal);
+        if denom.abs() < 1e-6 {
+            return None;
+        }
+        let t = (point - self.origin).dot(normal) / denom;
+        (t >= 0.0).then_some(t)
+    }
+
+    /// Closest approach to the line `point + s * dir` (`dir` normalized).
+    /// Returns the line parameter `s` and the distance between ray and line.
+    pub fn closest_to_line(&self, point: Vec3, dir: Vec3) -> Option<(f32, f32)> {
+        let w = self.origin - point;
+        let b = self.direction.dot(dir);
+        let denom = 1.0 - b * b;
+        if denom < 1e-6 {
+            return None;
+        }
+        let d = self.direction.dot(w);
+        let e = dir.dot(w);
+        let t = ((b * e - d) / denom).max(0.0);
+        let s = e + b * t;
+        Some((s, self.at(t).distance(point + dir * s)))
+    }
+}
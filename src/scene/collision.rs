@@ -0,0 +1,311 @@
+use glam::{Mat4, Vec2, Vec3, Vec3Swizzles};
+
+use crate::scene::bounds::{Aabb, Ray};
+
+/// Surfaces whose normal is at least this close to up count as floor.
+const MIN_FLOOR_NORMAL: f32 = 0.7;
+/// Push-out passes per resolve; corners need more than one.
+const RESOLVE_ITERATIONS: usize = 3;
+/// Cells along the longer horizontal side of a collider's grid.
+const GRID_CELLS: f32 = 32.0;
+
+#[derive(Copy, Clone, Debug)]
+pub struct Triangle {
+    pub a: Vec3,
+    pub b: Vec3,
+    pub c: Vec3,
+}
+
+impl Triangle {
+    pub fn new(a: Vec3, b: Vec3, c: Vec3) -> Self {
+        Self { a, b, c }
+    }
+
+    pub fn transformed(&self, m: Mat4) -> Triangle {
+        Triangle::new(m.transform_point3(self.a), m.transform_point3(self.b), m.transform_point3(self.c))
+    }
+
+    pub fn bounds(&self) -> Aabb {
+        Aabb::new(self.a.min(self.b).min(self.c), self.a.max(self.b).max(self.c))
+    }
+
+    /// Closest point on the triangle to `p` (Ericson, Real-Time Collision
+    /// Detection 5.1.5).
+    pub fn closest_point(&self, p: Vec3) -> Vec3 {
+        let (a, b, c) = (self.a, self.b, self.c);
+        let (ab, ac, ap) = (b - a, c - a, p - a);
+        let (d1, d2) = (ab.dot(ap), ac.dot(ap));
+        if d1 <= 0.0 && d2 <= 0.0 {
+            return a;
+        }
+        let bp = p - b;
+        let (d3, d4) = (ab.dot(bp), ac.dot(bp));
+        if d3 >= 0.0 && d4 <= d3 {
+            return b;
+        }
+        let vc = d1 * d4 - d3 * d2;
+        if vc <= 0.0 && d1 >= 0.0 && d3 <= 0.0 {
+            return a + ab * (d1 / (d1 - d3));
+        }
+        let cp = p - c;
+        let (d5, d6) = (ab.dot(cp), ac.dot(cp));
+        if d6 >= 0.0 && d5 <= d6 {
+            return c;
+        }
+        let vb = d5 * d2 - d1 * d6;
+        if vb <= 0.0 && d2 >= 0.0 && d6 <= 0.0 {
+            return a + ac * (d2 / (d2 - d6));
+        }
+        let va = d3 * d6 - d5 * d4;
+        if va <= 0.0 && d4 - d3 >= 0.0 && d5 - d6 >= 0.0 {
+            return b + (c - b) * ((d4 - d3) / ((d4 - d3) + (d5 - d6)));
+        }
+        let denom = 1.0 / (va + vb + vc);
+        a + ab * (vb * denom) + ac * (vc * denom)
+    }
+
+    /// Distance along `ray` to the triangle from either side
+    /// (Möller-Trumbore).
+    pub fn intersect(&self, ray: &Ray) -> Option<f32> {
+        let (e1, e2) = (self.b - self.a, self.c - self.a);
+        let p = ray.direction.cross(e2);
+        let det = e1.dot(p);
+        if det.abs() < 1e-8 {
+            return None;
+        }
+        let inv = 1.0 / det;
+        let s = ray.origin - self.a;
+        let u = s.dot(p) * inv;
+        if !(0.0..=1.0).contains(&u) {
+            return None;
+        }
+        let q = s.cross(e1);
+        let v = ray.direction.dot(q) * inv;
+        if v < 0.0 || u + v > 1.0 {
+            return None;
+        }
+        let t = e2.dot(q) * inv;
+        (t >= 0.0).then_some(t)
+    }
+}
+
+/// Triangles bucketed into a grid of columns over the horizontal plane, so
+/// queries only test the triangles near the capsule or under a point. Y is
+/// up, as in the frame the walk camera moves in.
+#[derive(Default)]
+pub struct Collider {
+    triangles: Vec<Triangle>,
+    /// Corner of the grid on the XZ plane.
+    origin: Vec2,
+    cell_size: f32,
+    columns: usize,
+    rows: usize,
+    /// Indices into `triangles` per cell, row by row.
+    cells: Vec<Vec<usize>>,
+}
+
+impl Collider {
+    pub fn new(triangles: Vec<Triangle>) -> Self {
+        let Some(bounds) = Aabb::from_points(triangles.iter().flat_map(|t| [t.a, t.b, t.c])) else {
+            return Self::default();
+        };
+        let (origin, extent) = (bounds.min.xz(), (bounds.max - bounds.min).xz());
+        let cell_size = (extent.max_element() / GRID_CELLS).max(f32::EPSILON);
+        let columns = (extent.x / cell_size).floor() as usize + 1;
+        let rows = (extent.y / cell_size).floor() as usize + 1;
+        let mut collider = Self {
+            triangles: Vec::new(),
+            origin,
+            cell_size,
+            columns,
+            rows,
+            cells: vec![Vec::new(); columns * rows],
+        };
+        for (i, tri) in triangles.iter().enumerate() {
+            let b = tri.bounds();
+            if let Some(range) = collider.cell_range(b.min.xz(), b.max.xz()) {
+                collider.for_cells(range, |cell| cell.push(i));
+            }
+        }
+        collider.triangles = triangles;
+        collider
+    }
+
+    /// Cells covering the XZ rectangle from `min` to `max`, as inclusive
+    /// column and row ranges, or `None` if it misses the grid.
+    fn cell_range(&self, min: Vec2, max: Vec2) -> Option<([usize; 2], [usize; 2])> {
+        if self.cells.is_empty() {
+            return None;
+        }
+        let (lo, hi) = ((min - self.origin) / self.cell_size, (max - self.origin) / self.cell_size);
+        let (columns, rows) = (self.columns as f32, self.rows as f32);
+        if hi.x < 0.0 || hi.y < 0.0 || lo.x >= columns || lo.y >= rows {
+            return None;
+        }
+        let index = |v: f32, count: usize| (v.max(0.0) as usize).min(count - 1);
+        Some((
+            [index(lo.x, self.columns), index(hi.x, self.columns)],
+            [index(lo.y, self.rows), index(hi.y, self.rows)],
+        ))
+    }
+
+    fn for_cells(&mut self, ([x0, x1], [z0, z1]): ([usize; 2], [usize; 2]), mut f: impl FnMut(&mut Vec<usize>)) {
+        for z in z0..=z1 {
+            for x in x0..=x1 {
+                f(&mut self.cells[z * self.columns + x]);
+            }
+        }
+    }
+
+    /// Triangles whose cells overlap `bounds` horizontally, each once.
+    fn near(&self, bounds: &Aabb) -> Vec<&Triangle> {
+        let Some(([x0, x1], [z0, z1])) = self.cell_range(bounds.min.xz(), bounds.max.xz()) else {
+            return Vec::new();
+        };
+        let mut indices: Vec<usize> = (z0..=z1)
+            .flat_map(|z| (x0..=x1).map(move |x| z * self.columns + x))
+            .flat_map(|cell| self.cells[cell].iter().copied())
+            .collect();
+        // Triangles spanning several cells are listed in each.
+        indices.sort_unstable();
+        indices.dedup();
+        indices
+            .into_iter()
+            .map(|i| &self.triangles[i])
+            .filter(|tri| tri.bounds().intersects(bounds))
+            .collect()
+    }
+}
+
+/// Vertical capsule: a cylinder with hemispherical ends between `bottom` and
+/// `top`, the centres of the end spheres.
+#[derive(Copy, Clone, Debug)]
+pub struct Capsule {
+    pub bottom: Vec3,
+    pub top: Vec3,
+    pub radius: f32,
+}
+
+/// Outcome of pushing a capsule out of geometry.
+pub struct Contact {
+    /// Total displacement applied to the capsule.
+    pub offset: Vec3,
+    /// Whether the capsule rests on a surface flat enough to stand on.
+    pub on_floor: bool,
+}
+
+impl Capsule {
+    /// Moves the capsule out of the collider's triangles. The capsule is
+    /// approximated by spheres spaced at most one radius apart along its
+    /// axis, which is plenty for walls and floors of architectural models.
+    pub fn resolve(&mut self, collider: &Collider) -> Contact {
+        let axis = self.top - self.bottom;
+        let count = (axis.length() / self.radius).ceil() as usize + 1;
+        let mut contact = Contact { offset: Vec3::ZERO, on_floor: false };
+        for _ in 0..RESOLVE_ITERATIONS {
+            let mut moved = false;
+            let triangles = collider.near(&self.bounds());
+            for i in 0..count {
+                let center = self.bottom + axis * (i as f32 / (count - 1).max(1) as f32);
+                for tri in &triangles {
+                    let away = center - tri.closest_point(center);
+                    let distance = away.length();
+                    if distance >= self.radius || distance <= f32::EPSILON {
+                        continue;
+                    }
+                    let normal = away / distance;
+                    let push = normal * (self.radius - distance);
+                    self.bottom += push;
+                    self.top += push;
+                    contact.offset += push;
+                    contact.on_floor |= normal.y >= MIN_FLOOR_NORMAL;
+                    moved = true;
+                }
+            }
+            if !moved {
+                break;
+            }
+        }
+        contact
+    }
+
+    fn bounds(&self) -> Aabb {
+        let r = Vec3::splat(self.radius);
+        Aabb::new(self.bottom.min(self.top) - r, self.bottom.max(self.top) + r)
+    }
+}
+
+/// Height of the highest surface below `point`, if any.
+pub fn floor_below(point: Vec3, collider: &Collider) -> Option<f32> {
+    let ray = Ray::new(point, Vec3::NEG_Y);
+    let column = Aabb::new(Vec3::new(point.x, f32::MIN, point.z), point);
+    collider
+        .near(&column)
+        .into_iter()
+        .filter_map(|tri| tri.intersect(&ray))
+        .min_by(f32::total_cmp)
+        .map(|t| point.y - t)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A 20 by 20 floor at y = 0 and a wall across it at x = 5.
+    fn room() -> Collider {
+        let (a, b, c, d) = (
+            Vec3::new(-10.0, 0.0, -10.0),
+            Vec3::new(10.0, 0.0, -10.0),
+            Vec3::new(10.0, 0.0, 10.0),
+            Vec3::new(-10.0, 0.0, 10.0),
+        );
+        let (e, f, g, h) = (
+            Vec3::new(5.0, 0.0, -10.0),
+            Vec3::new(5.0, 0.0, 10.0),
+            Vec3::new(5.0, 3.0, 10.0),
+            Vec3::new(5.0, 3.0, -10.0),
+        );
+        Collider::new(vec![
+            Triangle::new(a, b, c),
+            Triangle::new(a, c, d),
+            Triangle::new(e, f, g),
+            Triangle::new(e, g, h),
+        ])
+    }
+
+    #[test]
+    fn near_only_returns_triangles_around_the_query() {
+        let collider = room();
+        let by_the_wall = Aabb::new(Vec3::new(4.5, 1.0, 0.0), Vec3::new(5.5, 2.0, 1.0));
+        assert_eq!(collider.near(&by_the_wall).len(), 2);
+        let mid_room = Aabb::new(Vec3::new(-1.0, 1.0, -1.0), Vec3::new(1.0, 2.0, 1.0));
+        assert!(collider.near(&mid_room).is_empty());
+        let outside = Aabb::new(Vec3::splat(50.0), Vec3::splat(51.0));
+        assert!(collider.near(&outside).is_empty());
+    }
+
+    #[test]
+    fn floor_below_finds_the_highest_surface() {
+        let collider = room();
+        assert_eq!(floor_below(Vec3::new(1.0, 2.0, 1.0), &collider), Some(0.0));
+        assert_eq!(floor_below(Vec3::new(30.0, 2.0, 1.0), &collider), None);
+        assert_eq!(floor_below(Vec3::new(1.0, 2.0, 1.0), &Collider::default()), None);
+    }
+
+    #[test]
+    fn capsule_sunk_into_the_floor_is_pushed_up() {
+        let mut capsule = Capsule { bottom: Vec3::new(2.0, 0.2, -5.0), top: Vec3::new(2.0, 1.5, -5.0), radius: 0.3 };
+        let contact = capsule.resolve(&room());
+        assert!(contact.on_floor);
+        assert!((capsule.bottom.y - 0.3).abs() < 1e-4);
+    }
+
+    #[test]
+    fn capsule_is_pushed_out_of_a_wall_sideways() {
+        let mut capsule = Capsule { bottom: Vec3::new(4.9, 1.0, 0.0), top: Vec3::new(4.9, 2.0, 0.0), radius: 0.3 };
+        let contact = capsule.resolve(&room());
+        assert!(!contact.on_floor);
+        assert!((capsule.bottom.x - 4.7).abs() < 1e-4);
+        assert_eq!(contact.offset.y, 0.0);
+    }
+}
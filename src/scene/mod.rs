@@ -0,0 +1,4 @@
+pub mod bounds;
+pub mod collision;
+pub mod object;
+pub mod world;
@@ -0,0 +1,146 @@
+use glam::{Mat4, Quat, Vec3};
+
+use crate::scene::bounds::{Aabb, Ray};
+use crate::scene::collision::Triangle;
+use crate::scene::world::WorldFrame;
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Transform {
+    pub translation: Vec3,
+    pub rotation: Quat,
+    pub scale: Vec3,
+}
+
+impl Transform {
+    pub const IDENTITY: Self = Self {
+        translation: Vec3::ZERO,
+        rotation: Quat::IDENTITY,
+        scale: Vec3::ONE,
+    };
+
+    pub fn matrix(&self) -> Mat4 {
+        Mat4::from_scale_rotation_translation(self.scale, self.rotation, self.translation)
+    }
+}
+
+pub struct SceneObject {
+    pub name: String,
+    pub transform: Transform,
+    /// Extra local motion applied before `transform`, e.g. the demo spin.
+    pub animation: Mat4,
+    /// Convention the object's data was authored in; `None` if it is the
+    /// scene's. See `authored_in`.
+    pub authored: Option<WorldFrame>,
+    /// Conversion from `authored` into the scene's convention, applied
+    /// first. Kept up to date by `Scene`.
+    conversion: Mat4,
+    /// Bounds in the object's local space.
+    pub bounds: Aabb,
+    /// Local-space surface used for walk collisions.
+    pub triangles: Vec<Triangle>,
+}
+
+impl SceneObject {
+    pub fn new(name: &str, bounds: Aabb) -> Self {
+        Self {
+            name: name.to_string(),
+            transform: Transform::IDENTITY,
+            animation: Mat4::IDENTITY,
+            authored: None,
+            conversion: Mat4::IDENTITY,
+            bounds,
+            triangles: Vec::new(),
+        }
+    }
+
+    /// Marks the object's data as authored in `source` conventions, so it is
+    /// converted into the scene's when drawn, picked and collided with.
+    /// Importers call this with the file's up axis and handedness.
+    pub fn authored_in(mut self, source: WorldFrame) -> Self {
+        self.authored = Some(source);
+        self
+    }
+
+    pub fn model(&self) -> Mat4 {
+        self.transform.matrix() * self.animation * self.conversion
+    }
+
+    pub fn world_bounds(&self) -> Aabb {
+        self.bounds.transformed(self.model())
+    }
+
+    /// Distance along `ray` to the object's bounds, tested in local space.
+    pub fn intersect(&self, ray: &Ray) -> Option<f32> {
+        let inv = self.model().inverse();
+        let local = Ray::new(
+            inv.transform_point3(ray.origin),
+            inv.transform_vector3(ray.direction),
+        );
+        let t = self.bounds.intersect(&local)?;
+        Some(self.model().transform_point3(local.at(t)).distance(ray.origin))
+    }
+}
+
+#[derive(Default)]
+pub struct Scene {
+    pub objects: Vec<SceneObject>,
+    pub selected: Option<usize>,
+    pub hovered: Option<usize>,
+    frame: WorldFrame,
+}
+
+impl Scene {
+    pub fn add(&mut self, mut object: SceneObject) -> usize {
+        object.conversion = self.conversion(&object);
+        self.objects.push(object);
+        self.objects.len() - 1
+    }
+
+    /// Changes the scene's convention. Objects authored in another one are
+    /// converted anew, so they keep their orientation on screen.
+    pub fn set_world_frame(&mut self, frame: WorldFrame) {
+        self.frame = frame;
+        for i in 0..self.objects.len() {
+            self.objects[i].conversion = self.conversion(&self.objects[i]);
+        }
+    }
+
+    fn conversion(&self, object: &SceneObject) -> Mat4 {
+        object
+            .authored
+            .map_or(Mat4::IDENTITY, |source| self.frame.convert_from(source))
+    }
+
+    /// Index of the nearest object hit by `ray`.
+    pub fn pick(&self, ray: &Ray) -> Option<usize> {
+        self.objects
+            .iter()
+            .enumerate()
+            .filter_map(|(i, o)| o.intersect(ray).map(|t| (i, t)))
+            .min_by(|a, b| a.1.total_cmp(&b.1))
+            .map(|(i, _)| i)
+    }
+
+    /// World bounds enclosing all objects.
+    pub fn bounds(&self) -> Option<Aabb> {
+        self.objects
+            .iter()
+            .map(|o| o.world_bounds())
+            .reduce(|a, b| a.union(&b))
+    }
+
+    /// World-space collision triangles of all objects.
+    pub fn triangles(&self) -> Vec<Triangle> {
+        self.objects
+            .iter()
+            .flat_map(|o| {
+                let model = o.model();
+                o.triangles.iter().map(move |t| t.transformed(model))
+            })
+            .collect()
+    }
+
+    pub fn selected_object(&self) -> Option<&SceneObject> {
+        self.selected.and_then(|i| self.objects.get(i))
+    }
+}
@@ -0,0 +1,66 @@
+use glam::{Mat4, Vec3, Vec4};
+
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum UpAxis {
+    #[default]
+    Y,
+    Z,
+}
+
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum Handedness {
+    #[default]
+    Left,
+    Right,
+}
+
+/// Coordinate convention of world space. Cameras, grid and view cube work in
+/// a fixed Y-up left-handed display frame; this maps world coordinates into
+/// it so scenes authored e.g. Z-up right-handed (most CAD tools) show with
+/// their up axis pointing up.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct WorldFrame {
+    pub up: UpAxis,
+    pub handedness: Handedness,
+}
+
+impl WorldFrame {
+    /// World to display frame. Only permutes and negates axes, so the
+    /// inverse is the transpose.
+    pub fn to_display(&self) -> Mat4 {
+        // Column i is where world axis i ends up in the display frame.
+        let (x, y, z) = match (self.up, self.handedness) {
+            (UpAxis::Y, Handedness::Left) => (Vec3::X, Vec3::Y, Vec3::Z),
+            (UpAxis::Y, Handedness::Right) => (Vec3::X, Vec3::Y, Vec3::NEG_Z),
+            // Z-up: world +Y becomes display +Z, the direction the front
+            // view looks in, so it looks along +Y from -Y as in CAD tools.
+            (UpAxis::Z, Handedness::Right) => (Vec3::X, Vec3::Z, Vec3::Y),
+            (UpAxis::Z, Handedness::Left) => (Vec3::X, Vec3::NEG_Z, Vec3::Y),
+        };
+        Mat4::from_cols(x.extend(0.0), y.extend(0.0), z.extend(0.0), Vec4::W)
+    }
+
+    pub fn from_display(&self) -> Mat4 {
+        self.to_display().transpose()
+    }
+
+    /// World up direction.
+    pub fn up_vector(&self) -> Vec3 {
+        match self.up {
+            UpAxis::Y => Vec3::Y,
+            UpAxis::Z => Vec3::Z,
+        }
+    }
+
+    /// Whether the display of this world is mirrored, which reverses the
+    /// winding of triangles on screen.
+    pub fn is_mirrored(&self) -> bool {
+        self.handedness == Handedness::Right
+    }
+
+    /// Converts coordinates authored in the `source` convention into this
+    /// world, e.g. to bring a Z-up model into a Y-up scene on import.
+    pub fn convert_from(&self, source: WorldFrame) -> Mat4 {
+        self.from_display() * source.to_display()
+    }
+}
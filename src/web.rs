@@ -1,111 +1,1064 @@
 #![cfg(target_arch = "wasm32")]
 
-use std::{cell::RefCell, rc::Rc};
+use std::{cell::{Cell, RefCell}, collections::BTreeMap, rc::Rc};
 use wasm_bindgen::prelude::*;
 use wasm_bindgen::{closure::Closure, JsCast};
+use web_sys::{HtmlCanvasElement, OffscreenCanvas, PointerEvent, Window, Worker};
 
-use glam::Mat4;
+use glam::{Mat4, Vec3};
 
+use crate::host::{self, Host};
 use crate::input::active_camera::{ActiveCamera, CameraType};
+use crate::input::bindings::InputMap;
 use crate::input::camera::CameraController;
-use crate::input::{keyboard, mouse};
-use crate::render::state::State;
+use crate::input::camera_path::{CameraPath, Interpolation, Keyframe};
+use crate::input::camera_state::{self, CameraState};
+use crate::input::gamepad::{self, GamepadSettings};
+use crate::input::inertia::Inertia;
+use crate::input::projection::Projection;
+use crate::input::transform_gizmo::{GizmoMode, GizmoSpace, TransformGizmo};
+use crate::input::viewports::{Layout, Viewports};
+use crate::input::listener::Listener;
+use crate::input::{keyboard, mouse, picking, pointer_lock, transform_gizmo, view_cube, viewports};
+use crate::render::data;
+use crate::render::gpu::{self, LostListener};
+use crate::render::state::{DrawObject, Highlight, State};
+use crate::resize::{self, SizeObserver};
+use crate::scene::bounds::Aabb;
+use crate::scene::collision::Triangle;
+use crate::scene::object::{Scene, SceneObject};
+use crate::scene::world::{Handedness, UpAxis, WorldFrame};
 
-thread_local! {
-    static STATE: RefCell<Option<Rc<RefCell<State>>>> = RefCell::new(None);
-    static CAMERA: RefCell<Option<Rc<RefCell<ActiveCamera>>>> = RefCell::new(None);
+/// Longest time step simulated in one frame, in seconds. A tab returning
+/// from the background would otherwise integrate the whole pause at once,
+/// e.g. dropping the walk camera through the floor.
+const MAX_TIME_STEP: f32 = 0.1;
+
+/// Interval between polls of an idle gamepad while nothing is drawn.
+const GAMEPAD_POLL_MS: i32 = 100;
+
+/// Body of a frame loop, dropped when the loop stops.
+type FrameCallback = Rc<RefCell<Option<Closure<dyn FnMut()>>>>;
+
+/// The `requestAnimationFrame` loop of a viewer.
+#[derive(Clone)]
+struct FrameLoop {
+    host: Host,
+    callback: FrameCallback,
+    /// Id of the pending frame request.
+    request: Rc<Cell<Option<i32>>>,
 }
 
-#[wasm_bindgen]
-pub fn set_grid_visible(show: bool) {
-    STATE.with(|s| {
-        if let Some(st) = &*s.borrow() {
-            st.borrow_mut().set_grid_visible(show);
+impl FrameLoop {
+    fn new(host: Host) -> Self {
+        Self {
+            host,
+            callback: Rc::default(),
+            request: Rc::default(),
         }
-    });
+    }
+
+    /// Schedules a frame unless one is already pending.
+    fn request(&self) {
+        if self.request.get().is_some() {
+            return;
+        }
+        let Some(callback) = &*self.callback.borrow() else {
+            return;
+        };
+        if let Some(id) = self.host.request_animation_frame(callback.as_ref().unchecked_ref()) {
+            self.request.set(Some(id));
+        }
+    }
+
+    fn stop(&self) {
+        if let Some(id) = self.request.take() {
+            self.host.cancel_animation_frame(id);
+        }
+        // Drops the closure and with it the loop's references to the viewer.
+        self.callback.borrow_mut().take();
+    }
 }
 
-#[wasm_bindgen]
-pub fn set_camera_mode(mode: &str) {
-    CAMERA.with(|c| {
-        if let Some(cam) = &*c.borrow() {
-            let mut cam = cam.borrow_mut();
-            match mode {
-                "free" => cam.set_type(CameraType::Free),
-                "orbit" => cam.set_type(CameraType::Orbit),
-                _ => {}
+/// Checks a connected but idle gamepad a few times a second while the frame
+/// loop sleeps in on-demand mode. Gamepads have no input events, so this is
+/// what wakes the loop once the pad is used again.
+#[derive(Clone)]
+struct GamepadWake {
+    window: Window,
+    callback: FrameCallback,
+    /// Id of the pending poll.
+    timer: Rc<Cell<Option<i32>>>,
+}
+
+impl GamepadWake {
+    fn new(window: Window, frame: FrameLoop, settings: Rc<RefCell<GamepadSettings>>) -> Self {
+        let wake = Self {
+            window,
+            callback: Rc::default(),
+            timer: Rc::default(),
+        };
+        let wake_c = wake.clone();
+        *wake.callback.borrow_mut() = Some(Closure::wrap(Box::new(move || {
+            wake_c.timer.set(None);
+            let input = gamepad::poll(&wake_c.window, &settings.borrow());
+            match input {
+                Some(input) if !input.is_idle() => frame.request(),
+                Some(_) => wake_c.schedule(),
+                // Disconnected; connecting one goes with a click or key.
+                None => {}
             }
+        }) as Box<dyn FnMut()>));
+        wake
+    }
+
+    /// Polls after `GAMEPAD_POLL_MS` unless a poll is already pending.
+    fn schedule(&self) {
+        if self.timer.get().is_some() {
+            return;
         }
-    });
+        let Some(callback) = &*self.callback.borrow() else {
+            return;
+        };
+        let id = self.window.set_timeout_with_callback_and_timeout_and_arguments_0(
+            callback.as_ref().unchecked_ref(),
+            GAMEPAD_POLL_MS,
+        );
+        self.timer.set(id.ok());
+    }
+
+    fn stop(&self) {
+        if let Some(id) = self.timer.take() {
+            self.window.clear_timeout_with_handle(id);
+        }
+        self.callback.borrow_mut().take();
+    }
 }
 
-#[wasm_bindgen]
-pub fn resize(width: u32, height: u32) {
-    STATE.with(|s| {
-        if let Some(st) = &*s.borrow() {
-            st.borrow_mut().resize(width, height);
+/// Drawing buffer size of a viewer: the canvas size in device pixels times
+/// the render scale, within the texture size limit of the device.
+#[derive(Clone)]
+struct Resolution {
+    host: Host,
+    state: Rc<RefCell<State>>,
+    camera: Rc<RefCell<Viewports>>,
+    frame: FrameLoop,
+    /// Size of the canvas in device pixels.
+    device_size: Rc<Cell<(u32, u32)>>,
+    /// Fraction of the device pixels drawn; below 1 trades sharpness for
+    /// speed.
+    scale: Rc<Cell<f32>>,
+}
+
+impl Resolution {
+    fn apply(&self) {
+        let (width, height) = self.device_size.get();
+        if width == 0 || height == 0 {
+            return;
+        }
+        let scale = self.scale.get();
+        let (w, h) = (width as f32 * scale, height as f32 * scale);
+        // Both sides shrink alike so the limit keeps the aspect ratio.
+        let fit = (self.state.borrow().max_size() as f32 / w.max(h)).min(1.0);
+        let (w, h) = (((w * fit).round() as u32).max(1), ((h * fit).round() as u32).max(1));
+        self.host.set_canvas_size(w, h);
+        self.state.borrow_mut().resize(w, h);
+        self.camera.borrow_mut().set_aspect(width as f32 / height as f32);
+        self.frame.request();
+    }
+}
+
+/// Delay before the first retry of a failed device recovery; it doubles with
+/// every further failure up to `MAX_RECOVER_DELAY_MS`.
+const RECOVER_DELAY_MS: i32 = 500;
+const MAX_RECOVER_DELAY_MS: i32 = 30_000;
+
+/// Replaces a renderer whose device was lost with one on a new device, then
+/// resumes the frame loop. A failed attempt is retried with a growing delay,
+/// so a briefly unavailable adapter does not leave the viewer blank for good.
+/// `recovering` is cleared once a device is back or the viewer was disposed.
+fn recover(host: &Host, state: Rc<RefCell<State>>, frame: FrameLoop, recovering: Rc<Cell<bool>>) {
+    let canvas = host.render_canvas();
+    wasm_bindgen_futures::spawn_local(async move {
+        let mut delay = RECOVER_DELAY_MS;
+        let mut fresh = loop {
+            match State::new(&canvas).await {
+                Ok(fresh) => break fresh,
+                Err(e) => {
+                    web_sys::console::error_2(&"Could not recover from a lost GPU device:".into(), &e);
+                }
+            }
+            sleep(delay).await;
+            delay = (delay * 2).min(MAX_RECOVER_DELAY_MS);
+            if frame.callback.borrow().is_none() {
+                recovering.set(false);
+                return;
+            }
+        };
+        recovering.set(false);
+        // Disposed while waiting for the device.
+        if frame.callback.borrow().is_none() {
+            fresh.destroy();
+            return;
         }
+        fresh.restore(&state.borrow());
+        *state.borrow_mut() = fresh;
+        frame.request();
     });
-    CAMERA.with(|c| {
-        if let Some(cam) = &*c.borrow() {
-            cam.borrow_mut().set_aspect(width as f32 / height as f32);
+}
+
+/// Resolves after `ms` milliseconds, on a page as well as in a worker.
+async fn sleep(ms: i32) {
+    let promise = js_sys::Promise::new(&mut |resolve, _| {
+        let global = js_sys::global();
+        let set_timeout = js_sys::Reflect::get(&global, &"setTimeout".into())
+            .ok()
+            .and_then(|f| f.dyn_into::<js_sys::Function>().ok());
+        match set_timeout {
+            Some(set_timeout) => {
+                let _ = set_timeout.call2(&global, &resolve, &ms.into());
+            }
+            None => {
+                let _ = resolve.call0(&JsValue::NULL);
+            }
         }
     });
+    let _ = wasm_bindgen_futures::JsFuture::from(promise).await;
 }
 
 #[wasm_bindgen(start)]
-pub async fn start() -> Result<(), JsValue> {
+pub fn start() {
     console_error_panic_hook::set_once();
-    let window = web_sys::window().unwrap();
-    let document = window.document().unwrap();
-    let canvas = document
-        .get_element_by_id("gpu-canvas")
-        .unwrap()
-        .dyn_into::<web_sys::HtmlCanvasElement>()?;
-
-    let state = Rc::new(RefCell::new(State::new(&canvas).await?));
-    STATE.with(|s| *s.borrow_mut() = Some(state.clone()));
-    let performance = window.performance().unwrap();
-    let aspect = state.borrow().aspect;
-    let camera = Rc::new(RefCell::new(ActiveCamera::new(aspect)));
-    CAMERA.with(|c| *c.borrow_mut() = Some(camera.clone()));
-
-    keyboard::attach(&window, camera.clone());
-    mouse::attach(&window, &canvas, camera.clone());
-
-    let start_time = performance.now();
-    let prev_time = Rc::new(RefCell::new(start_time));
-    let f: Rc<RefCell<Option<Closure<dyn FnMut()>>>> = Rc::new(RefCell::new(None));
-    let g = f.clone();
-    let window_c = window.clone();
-    let perf_c = performance.clone();
-    let camera_c = camera.clone();
-    let state_c = state.clone();
-    let prev_time_c = prev_time.clone();
-
-    *g.borrow_mut() = Some(Closure::wrap(Box::new(move || {
-        let now = perf_c.now();
-        let dt = (now - *prev_time_c.borrow()) as f32 / 1000.0;
-        *prev_time_c.borrow_mut() = now;
-        let elapsed = (now - start_time) as f32 / 1000.0;
-        let angle = elapsed / 5.0 * (2.0 * std::f32::consts::PI);
-        {
-            let mut cam = camera_c.borrow_mut();
-            cam.update(dt);
-            let cam_pos = cam.position();
-            let cam_matrix = cam.matrix();
-            let model = Mat4::from_rotation_z(angle);
-            let mut st = state_c.borrow_mut();
-            st.update(cam_matrix, model, cam_pos);
-            if st.render().is_err() {
+}
+
+/// A viewer drawing into one canvas. A page can create several; they share
+/// one GPU device but have their own scene, cameras and input. A viewer can
+/// also run in a worker, see `create_offscreen`.
+#[wasm_bindgen]
+pub struct Viewer {
+    host: Host,
+    state: Rc<RefCell<State>>,
+    camera: Rc<RefCell<Viewports>>,
+    gizmo: Rc<RefCell<TransformGizmo>>,
+    scene: Rc<RefCell<Scene>>,
+    path: Rc<RefCell<CameraPath>>,
+    bookmarks: BTreeMap<String, CameraState>,
+    /// URL hash last written by `share_view`.
+    shared_hash: Rc<RefCell<Option<String>>>,
+    gamepad: Rc<RefCell<GamepadSettings>>,
+    input_map: Rc<RefCell<InputMap>>,
+    listeners: Vec<Listener>,
+    frame: FrameLoop,
+    resolution: Resolution,
+    /// Follows the canvas size on the page; viewers in a worker get it from
+    /// the page instead.
+    size_observer: Option<SizeObserver>,
+    /// Resumes the frame loop when the device is lost, so the renderer is
+    /// replaced even while the viewer is idle.
+    lost_listener: Option<LostListener>,
+    /// Wakes the sleeping frame loop when an idle gamepad is used; `None`
+    /// in a worker, which cannot read gamepads.
+    gamepad_wake: Option<GamepadWake>,
+    /// Only draw frames when something changed instead of continuously.
+    on_demand: Rc<Cell<bool>>,
+    /// Whether the cube turns on its own.
+    spin: Rc<Cell<bool>>,
+    disposed: bool,
+}
+
+impl Viewer {
+    /// Runs `f` on the camera of the active viewport.
+    fn with_camera<R>(&self, f: impl FnOnce(&mut ActiveCamera) -> R) -> R {
+        self.frame.request();
+        f(self.camera.borrow_mut().active_mut())
+    }
+
+    /// Swaps the key bindings. Held actions are released first, since the
+    /// `keyup` of their keys may no longer map to them.
+    fn set_input_map(&self, map: InputMap) {
+        self.camera.borrow_mut().release_actions();
+        *self.input_map.borrow_mut() = map;
+    }
+
+    /// Runs `f` on the main perspective camera, the one whose state is
+    /// saved, bookmarked, shared and driven by camera paths.
+    fn with_main_camera<R>(&self, f: impl FnOnce(&mut ActiveCamera) -> R) -> R {
+        self.frame.request();
+        f(self.camera.borrow_mut().main_mut())
+    }
+
+    /// Reads the main camera without drawing a frame, for getters that a
+    /// page may poll.
+    fn read_main_camera<R>(&self, f: impl FnOnce(&ActiveCamera) -> R) -> R {
+        f(self.camera.borrow().main())
+    }
+
+    /// Runs `f` on the camera of every viewport, for settings shared by all.
+    fn with_cameras(&self, mut f: impl FnMut(&mut ActiveCamera)) {
+        self.frame.request();
+        for view in self.camera.borrow_mut().views_mut() {
+            f(&mut view.camera);
+        }
+    }
+
+    fn with_path<R>(&self, f: impl FnOnce(&mut CameraPath) -> R) -> R {
+        self.frame.request();
+        f(&mut self.path.borrow_mut())
+    }
+
+    /// Reads the camera path without drawing a frame.
+    fn read_path<R>(&self, f: impl FnOnce(&CameraPath) -> R) -> R {
+        f(&self.path.borrow())
+    }
+
+    fn with_state<R>(&self, f: impl FnOnce(&mut State) -> R) -> R {
+        self.frame.request();
+        f(&mut self.state.borrow_mut())
+    }
+
+    fn with_gizmo(&self, f: impl FnOnce(&mut TransformGizmo)) {
+        self.frame.request();
+        f(&mut self.gizmo.borrow_mut());
+    }
+
+    /// Redraws on input. Drags and mouse look continue outside the canvas,
+    /// so moves over the window count while a button is held or the pointer
+    /// is locked.
+    fn wake_on_input(&mut self) {
+        let host = &self.host;
+        for event in ["pointerdown", "pointermove", "pointerleave", "wheel"] {
+            let frame = self.frame.clone();
+            self.listeners.push(Listener::new(host.canvas_events(), event, move |_: web_sys::Event| {
+                frame.request()
+            }));
+        }
+        for event in ["pointerup", "pointercancel", "keydown", "keyup", "hashchange"] {
+            let frame = self.frame.clone();
+            self.listeners.push(Listener::new(host.window_events(), event, move |_: web_sys::Event| {
+                frame.request()
+            }));
+        }
+        let frame = self.frame.clone();
+        let host_c = host.clone();
+        self.listeners.push(Listener::new(host.window_events(), "pointermove", move |e: PointerEvent| {
+            if e.buttons() != 0 || host_c.is_pointer_locked() {
+                frame.request();
+            }
+        }));
+    }
+
+    async fn start(host: Host, focused: Rc<Cell<bool>>) -> Result<Viewer, JsValue> {
+        let canvas = host.render_canvas();
+        let state = Rc::new(RefCell::new(State::new(&canvas).await?));
+        let aspect = state.borrow().aspect;
+        let camera = Rc::new(RefCell::new(Viewports::new(aspect)));
+        let frame = FrameLoop::new(host.clone());
+        let resolution = Resolution {
+            host: host.clone(),
+            state: state.clone(),
+            camera: camera.clone(),
+            frame: frame.clone(),
+            device_size: Rc::new(Cell::new(canvas.size())),
+            scale: Rc::new(Cell::new(1.0)),
+        };
+        let lost_listener = {
+            let frame = frame.clone();
+            gpu::on_device_lost(move || frame.request())
+        };
+        let mut scene = Scene::default();
+        // The built-in model is authored Y-up left-handed, like the display.
+        let mut cube = SceneObject::new("cube", Aabb::new(Vec3::splat(-0.5), Vec3::splat(0.5)))
+            .authored_in(WorldFrame::default());
+        let corner = |i: u16| Vec3::from(data::VERTICES[i as usize].position);
+        cube.triangles = data::INDICES
+            .chunks(3)
+            .map(|t| Triangle::new(corner(t[0]), corner(t[1]), corner(t[2])))
+            .collect();
+        scene.add(cube);
+        let mut viewer = Viewer {
+            host: host.clone(),
+            state,
+            camera,
+            gizmo: Rc::new(RefCell::new(TransformGizmo::new())),
+            scene: Rc::new(RefCell::new(scene)),
+            path: Rc::new(RefCell::new(CameraPath::new())),
+            bookmarks: BTreeMap::new(),
+            shared_hash: Rc::default(),
+            gamepad: Rc::new(RefCell::new(GamepadSettings::default())),
+            input_map: Rc::new(RefCell::new(InputMap::default())),
+            listeners: Vec::new(),
+            frame,
+            resolution,
+            size_observer: None,
+            lost_listener: Some(lost_listener),
+            gamepad_wake: None,
+            on_demand: Rc::new(Cell::new(false)),
+            spin: Rc::new(Cell::new(true)),
+            disposed: false,
+        };
+
+        let camera = viewer.camera.clone();
+        let scene = viewer.scene.clone();
+        let listeners = &mut viewer.listeners;
+        let input_map = viewer.input_map.clone();
+        listeners.extend(keyboard::attach(&host, focused.clone(), camera.clone(), input_map));
+        listeners.extend(viewports::attach(&host, camera.clone()));
+        listeners.extend(view_cube::attach(&host, camera.clone()));
+        listeners.extend(pointer_lock::attach(&host, camera.clone()));
+        let gizmo = viewer.gizmo.clone();
+        listeners.extend(transform_gizmo::attach(&host, camera.clone(), scene.clone(), gizmo));
+        listeners.extend(picking::attach(&host, camera.clone(), scene));
+        listeners.extend(mouse::attach(&host, camera));
+
+        viewer.wake_on_input();
+        viewer.run(focused);
+        Ok(viewer)
+    }
+
+    /// Starts the frame loop. In on-demand mode it stops once nothing moves
+    /// and input or API calls start it again.
+    fn run(&mut self, focused: Rc<Cell<bool>>) {
+        let performance = self.host.performance().unwrap();
+        // Time of the previous frame, `None` after the loop went idle so the
+        // pause does not count as a time step.
+        let prev_time = Rc::new(Cell::new(None::<f64>));
+        let mut spin_angle = 0.0f32;
+        // Object placements the collision geometry was built for.
+        let mut collision_models = Vec::new();
+        let frame = self.frame.clone();
+        let host = self.host.clone();
+        let camera_c = self.camera.clone();
+        let state_c = self.state.clone();
+        let scene_c = self.scene.clone();
+        let gizmo_c = self.gizmo.clone();
+        let path_c = self.path.clone();
+        let gamepad_c = self.gamepad.clone();
+        let on_demand = self.on_demand.clone();
+        let spin = self.spin.clone();
+        let recovering = Rc::new(Cell::new(false));
+        self.gamepad_wake = self
+            .host
+            .window()
+            .map(|window| GamepadWake::new(window.clone(), self.frame.clone(), self.gamepad.clone()));
+        let gamepad_wake = self.gamepad_wake.clone();
+
+        *self.frame.callback.borrow_mut() = Some(Closure::wrap(Box::new(move || {
+            frame.request.set(None);
+            if state_c.borrow().is_device_lost() {
+                // Pauses until a new device is ready; the wait is no time step.
+                if !recovering.replace(true) {
+                    recover(&host, state_c.clone(), frame.clone(), recovering.clone());
+                }
+                prev_time.set(None);
                 return;
             }
+            let now = performance.now();
+            let dt = prev_time
+                .get()
+                .map_or(0.0, |prev| ((now - prev) as f32 / 1000.0).min(MAX_TIME_STEP));
+            prev_time.set(Some(now));
+            if spin.get() {
+                spin_angle += dt / 5.0 * (2.0 * std::f32::consts::PI);
+            }
+            {
+                let mut cams = camera_c.borrow_mut();
+                // Only the viewer last clicked follows the gamepad. Workers
+                // cannot read gamepads.
+                let pad = host
+                    .window()
+                    .filter(|_| focused.get())
+                    .and_then(|window| gamepad::poll(window, &gamepad_c.borrow()));
+                if let Some(input) = pad.as_ref().filter(|i| !i.is_idle()) {
+                    cams.analog(input, dt);
+                }
+                cams.update(dt);
+                let mut path = path_c.borrow_mut();
+                if let Some(key) = path.update(dt) {
+                    cams.main_mut().look_at(key.position, key.target, key.fov_y);
+                }
+                let mut scene = scene_c.borrow_mut();
+                let world_frame = cams.main_mut().world_frame();
+                scene.objects[0].animation = Mat4::from_axis_angle(world_frame.up_vector(), spin_angle);
+                let model = scene.objects[0].model();
+                let mut st = state_c.borrow_mut();
+                st.set_mirrored(world_frame.is_mirrored() != (model.determinant() < 0.0));
+                // Only walk cameras collide. The demo spin moves the cube
+                // every frame, so walking while it spins still rebuilds the
+                // collider each frame; the other cameras skip that.
+                let walking = cams.views().iter().any(|v| v.camera.camera_type() == CameraType::Walk);
+                if walking {
+                    let models: Vec<Mat4> = scene.objects.iter().map(|o| o.model()).collect();
+                    if models != collision_models {
+                        cams.set_collision(scene.triangles());
+                        collision_models = models;
+                    }
+                }
+                let path_bounds = Aabb::from_points(
+                    path.keyframes().iter().flat_map(|k| [k.position, k.target]),
+                )
+                .filter(|_| path.visible);
+                let bounds = [scene.bounds(), st.helper_bounds(), path_bounds]
+                    .into_iter()
+                    .flatten()
+                    .reduce(|a, b| a.union(&b));
+                let highlight = |i| {
+                    if scene.selected == Some(i) {
+                        Highlight::Selected
+                    } else if scene.hovered == Some(i) {
+                        Highlight::Hovered
+                    } else {
+                        Highlight::None
+                    }
+                };
+                st.set_objects(
+                    scene
+                        .objects
+                        .iter()
+                        .enumerate()
+                        .map(|(i, o)| DrawObject { model: o.model(), highlight: highlight(i) })
+                        .collect(),
+                );
+                for (i, view) in cams.views_mut().iter_mut().enumerate() {
+                    let cam = &mut view.camera;
+                    if let Some(bounds) = bounds.filter(|_| cam.lens().auto_clip) {
+                        cam.fit_clip_planes(&bounds);
+                    }
+                    st.update(i, cam.matrix(), cam.position());
+                    st.update_view_cube(i, view_cube::matrix(cam.view()));
+                }
+                // The gizmo keeps its size relative to the viewport being used.
+                let cam_pos = cams.position();
+                let gizmo_vertices = scene
+                    .selected_object()
+                    .map(|o| gizmo_c.borrow().vertices(&o.transform, cam_pos))
+                    .unwrap_or_default();
+                st.set_gizmo_vertices(&gizmo_vertices);
+                st.set_path_vertices(&path.vertices());
+                // A frame that could not be drawn is retried on the next one.
+                let drawn = match st.render() {
+                    Ok(()) => true,
+                    Err(wgpu::SurfaceError::Lost | wgpu::SurfaceError::Outdated) => {
+                        st.reconfigure();
+                        false
+                    }
+                    Err(wgpu::SurfaceError::Timeout | wgpu::SurfaceError::Other) => false,
+                    // A new device starts with its memory free again.
+                    Err(wgpu::SurfaceError::OutOfMemory) => {
+                        web_sys::console::warn_1(&"Out of GPU memory, replacing the device".into());
+                        st.lose_device();
+                        false
+                    }
+                };
+                let animating = !drawn
+                    || cams.is_animating()
+                    || path.is_playing()
+                    || spin.get()
+                    || pad.as_ref().is_some_and(|i| !i.is_idle());
+                if on_demand.get() && !animating {
+                    prev_time.set(None);
+                    // Gamepads have no input events, so an idle one is
+                    // polled slowly instead of drawing every frame.
+                    if let Some(wake) = gamepad_wake.as_ref().filter(|_| pad.is_some()) {
+                        wake.schedule();
+                    }
+                    return;
+                }
+            }
+            frame.request();
+        }) as Box<dyn FnMut()>));
+        self.frame.request();
+    }
+}
+
+#[wasm_bindgen]
+impl Viewer {
+    /// Starts a viewer in `canvas`. The first viewer on the page requests
+    /// the GPU device, later ones reuse it.
+    pub async fn create(canvas: HtmlCanvasElement) -> Result<Viewer, JsValue> {
+        let window = web_sys::window().unwrap();
+        let (focused, focus_listener) = keyboard::focus(&window, &canvas);
+        let host = Host::Page {
+            window: window.clone(),
+            canvas: canvas.clone(),
+        };
+        let mut viewer = Viewer::start(host, focused).await?;
+        viewer.listeners.insert(0, focus_listener);
+        let resolution = viewer.resolution.clone();
+        viewer.size_observer = Some(resize::observe(&canvas, move |width, height| {
+            resolution.device_size.set((width, height));
+            resolution.apply();
+        })?);
+        viewer
+            .listeners
+            .extend(camera_state::attach(&window, viewer.camera.clone(), viewer.shared_hash.clone()));
+        Ok(viewer)
+    }
+
+    /// Starts a viewer inside a worker, drawing into a canvas the page
+    /// transferred with `transferControlToOffscreen`. The page forwards its
+    /// input with an `InputForwarder`; pass every message from it to
+    /// `handle_message`. Keeps heavy scenes from blocking the page.
+    pub async fn create_offscreen(canvas: OffscreenCanvas) -> Result<Viewer, JsValue> {
+        // The page only forwards keys while the canvas has focus.
+        Viewer::start(Host::worker(canvas)?, Rc::new(Cell::new(true))).await
+    }
+
+    /// Handles a message posted by the page's `InputForwarder`.
+    pub fn handle_message(&self, message: JsValue) {
+        if let Some((width, height)) = self.host.receive(&message) {
+            self.resize(width, height);
+        }
+    }
+
+    /// Stops rendering, removes all event listeners and frees the GPU
+    /// resources of this viewer, e.g. when the page navigates away from it.
+    /// The viewer must not be used afterwards. `free()` disposes as well.
+    pub fn dispose(&mut self) {
+        if self.disposed {
+            return;
         }
-        window_c
-            .request_animation_frame(f.borrow().as_ref().unwrap().as_ref().unchecked_ref())
-            .unwrap();
-    }) as Box<dyn FnMut()>));
+        self.disposed = true;
+        self.frame.stop();
+        if let Some(wake) = self.gamepad_wake.take() {
+            wake.stop();
+        }
+        self.listeners.clear();
+        self.size_observer = None;
+        self.lost_listener = None;
+        self.state.borrow_mut().destroy();
+    }
+
+    /// Draws a frame soon, for changes the viewer cannot see itself. Only
+    /// needed in on-demand mode; frames already pending are not duplicated.
+    pub fn request_redraw(&self) {
+        self.frame.request();
+    }
+
+    /// Switches between drawing every animation frame and drawing only when
+    /// input, camera motion, animation or API calls change the picture,
+    /// which saves power while the view is still.
+    pub fn set_render_on_demand(&self, enabled: bool) {
+        self.on_demand.set(enabled);
+        self.frame.request();
+    }
+
+    /// Whether frames are still being drawn because something moves.
+    pub fn is_animating(&self) -> bool {
+        self.frame.request.get().is_some()
+    }
+
+    /// Turns the automatic rotation of the cube on or off.
+    pub fn set_spin(&self, enabled: bool) {
+        self.spin.set(enabled);
+        self.frame.request();
+    }
+
+    pub fn set_grid_visible(&self, show: bool) {
+        self.with_state(|st| st.set_grid_visible(show));
+    }
+
+    /// Colour of the outline drawn around the selected object.
+    pub fn set_outline_color(&self, r: f32, g: f32, b: f32, a: f32) {
+        self.with_state(|st| st.outline.selected = [r, g, b, a]);
+    }
+
+    /// Colour of the outline drawn around the object under the pointer.
+    pub fn set_hover_color(&self, r: f32, g: f32, b: f32, a: f32) {
+        self.with_state(|st| st.outline.hovered = [r, g, b, a]);
+    }
+
+    /// Outline thickness in device pixels.
+    pub fn set_outline_width(&self, width: f32) {
+        self.with_state(|st| st.outline.width = width.max(0.0));
+    }
+
+    pub fn set_camera_mode(&self, mode: &str) {
+        self.with_camera(|cam| {
+            match mode {
+                "free" => cam.set_type(CameraType::Free),
+                "orbit" => cam.set_type(CameraType::Orbit),
+                "arcball" => cam.set_type(CameraType::Arcball),
+                "walk" => cam.set_type(CameraType::Walk),
+                _ => {}
+            }
+            if !cam.wants_pointer_lock() {
+                self.host.unlock_pointer();
+            }
+        });
+    }
+
+    /// Sets the projection of the active camera: `"perspective"` or `"orthographic"`.
+    pub fn set_projection(&self, mode: &str) {
+        self.with_camera(|cam| match mode {
+            "perspective" => cam.lens_mut().projection = Projection::Perspective,
+            "orthographic" => cam.lens_mut().projection = Projection::Orthographic,
+            _ => {}
+        });
+    }
+
+    /// Switches the active camera between perspective and orthographic and
+    /// returns the new mode.
+    pub fn toggle_projection(&self) -> String {
+        self.with_camera(|cam| {
+            let next = match cam.lens().projection {
+                Projection::Perspective => Projection::Orthographic,
+                Projection::Orthographic => Projection::Perspective,
+            };
+            cam.lens_mut().projection = next;
+            match next {
+                Projection::Perspective => "perspective".into(),
+                Projection::Orthographic => "orthographic".into(),
+            }
+        })
+    }
+
+    /// Splits the canvas: `"single"`, `"quad"` (top, front, side and
+    /// perspective) or `"split"` (two perspective views side by side). Input
+    /// goes to the viewport under the pointer.
+    pub fn set_layout(&self, layout: &str) {
+        let layout = match layout {
+            "single" => Layout::Single,
+            "quad" => Layout::Quad,
+            "split" => Layout::Split,
+            _ => return,
+        };
+        let mut cam = self.camera.borrow_mut();
+        cam.set_layout(layout);
+        self.with_state(|st| st.set_viewports(&cam.rects()));
+    }
+
+    /// Moves the orbit camera so the whole scene is in view.
+    pub fn frame_all(&self) {
+        if let Some(bounds) = self.scene.borrow().bounds() {
+            self.with_camera(|cam| cam.frame_bounds(&bounds));
+        }
+    }
+
+    /// Moves the orbit camera so the selected object is in view.
+    pub fn frame_selected(&self) {
+        if let Some(object) = self.scene.borrow().selected_object() {
+            let bounds = object.world_bounds();
+            self.with_camera(|cam| cam.frame_bounds(&bounds));
+        }
+    }
+
+    /// Complete state of the main camera as JSON: active camera type,
+    /// poses and projection.
+    pub fn get_camera_state(&self) -> String {
+        self.read_main_camera(|cam| cam.state().to_json())
+    }
+
+    /// Restores a camera state in the format of `get_camera_state`.
+    pub fn set_camera_state(&self, json: &str) -> Result<(), JsValue> {
+        let state = CameraState::from_json(json).map_err(|e| JsValue::from_str(&e))?;
+        self.with_main_camera(|cam| cam.set_state(&state, true));
+        Ok(())
+    }
+
+    /// Stores the main view under `name`, replacing an existing bookmark.
+    pub fn save_bookmark(&mut self, name: &str) {
+        let state = self.read_main_camera(|cam| cam.state());
+        self.bookmarks.insert(name.to_string(), state);
+    }
+
+    /// Moves to the bookmarked view. Returns false if there is no such bookmark.
+    pub fn go_to_bookmark(&self, name: &str) -> bool {
+        match self.bookmarks.get(name).copied() {
+            Some(state) => {
+                self.with_main_camera(|cam| cam.set_state(&state, true));
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn delete_bookmark(&mut self, name: &str) {
+        self.bookmarks.remove(name);
+    }
+
+    /// All bookmarks as a JSON object from name to camera state, for storing
+    /// them between sessions.
+    pub fn get_bookmarks(&self) -> String {
+        serde_json::to_string(&self.bookmarks).unwrap_or_default()
+    }
+
+    /// Replaces all bookmarks from JSON in the format of `get_bookmarks`.
+    pub fn set_bookmarks(&mut self, json: &str) -> Result<(), JsValue> {
+        let bookmarks: BTreeMap<String, CameraState> =
+            serde_json::from_str(json).map_err(|e| JsValue::from_str(&e.to_string()))?;
+        for (name, state) in &bookmarks {
+            state
+                .validate()
+                .map_err(|e| JsValue::from_str(&format!("bookmark {name}: {e}")))?;
+        }
+        self.bookmarks = bookmarks;
+        Ok(())
+    }
+
+    /// Writes the main view into the URL hash and returns the full URL, which
+    /// opens the viewer at the same viewpoint.
+    pub fn share_view(&self) -> Result<String, JsValue> {
+        let window = self
+            .host
+            .window()
+            .ok_or_else(|| JsValue::from_str("share_view is not available in a worker"))?;
+        let hash = self.read_main_camera(|cam| cam.state().to_hash());
+        let location = window.location();
+        // The view is already showing, so the resulting `hashchange` is
+        // skipped.
+        *self.shared_hash.borrow_mut() = Some(hash.clone());
+        location.set_hash(&hash)?;
+        location.href()
+    }
+
+    /// Adds the main view as a camera path keyframe at `time` seconds,
+    /// replacing a keyframe at the same time.
+    pub fn path_add_keyframe(&self, time: f32) {
+        let key = self.read_main_camera(|cam| Keyframe {
+            time,
+            position: cam.position(),
+            target: cam.target(),
+            fov_y: cam.lens().fov_y,
+        });
+        self.with_path(|path| path.add(key));
+    }
+
+    pub fn path_clear(&self) {
+        self.with_path(|path| path.clear());
+    }
+
+    /// Keyframes as JSON, for saving a path.
+    pub fn get_path(&self) -> String {
+        self.read_path(|path| serde_json::to_string(path.keyframes()).unwrap_or_default())
+    }
+
+    /// Replaces the keyframes from JSON in the format of `get_path`. Fails
+    /// without changing the path if a keyframe is unusable.
+    pub fn set_path(&self, json: &str) -> Result<(), JsValue> {
+        let keyframes: Vec<Keyframe> =
+            serde_json::from_str(json).map_err(|e| JsValue::from_str(&e.to_string()))?;
+        for key in &keyframes {
+            key.validate().map_err(|e| JsValue::from_str(&e))?;
+        }
+        self.with_path(|path| path.set_keyframes(keyframes));
+        Ok(())
+    }
+
+    pub fn path_play(&self) {
+        self.with_path(|path| path.play());
+    }
+
+    pub fn path_pause(&self) {
+        self.with_path(|path| path.pause());
+    }
+
+    /// Moves playback to `time` seconds and the camera with it.
+    pub fn path_seek(&self, time: f32) {
+        self.with_path(|path| path.seek(time));
+    }
+
+    pub fn path_set_loop(&self, looping: bool) {
+        self.with_path(|path| path.looping = looping);
+    }
+
+    /// "catmull-rom" or "bezier".
+    pub fn path_set_interpolation(&self, mode: &str) {
+        let interpolation = match mode {
+            "catmull-rom" => Interpolation::CatmullRom,
+            "bezier" => Interpolation::Bezier,
+            _ => return,
+        };
+        self.with_path(|path| path.interpolation = interpolation);
+    }
+
+    /// Shows or hides the path as lines in the viewport.
+    pub fn path_set_visible(&self, visible: bool) {
+        self.with_path(|path| path.visible = visible);
+    }
+
+    pub fn path_time(&self) -> f32 {
+        self.read_path(|path| path.time())
+    }
+
+    pub fn path_duration(&self) -> f32 {
+        self.read_path(|path| path.duration())
+    }
+
+    pub fn path_is_playing(&self) -> bool {
+        self.read_path(|path| path.is_playing())
+    }
+
+    /// World coordinate convention: `up` is "y" or "z", `handedness` is "left"
+    /// or "right". CAD data is usually "z" and "right".
+    pub fn set_world_frame(&self, up: &str, handedness: &str) -> Result<(), JsValue> {
+        let up = match up {
+            "y" => UpAxis::Y,
+            "z" => UpAxis::Z,
+            _ => return Err(JsValue::from_str("up axis must be \"y\" or \"z\"")),
+        };
+        let handedness = match handedness {
+            "left" => Handedness::Left,
+            "right" => Handedness::Right,
+            _ => return Err(JsValue::from_str("handedness must be \"left\" or \"right\"")),
+        };
+        let frame = WorldFrame { up, handedness };
+        self.with_cameras(|cam| cam.set_world_frame(frame));
+        self.with_state(|st| st.set_world_frame(frame));
+        self.scene.borrow_mut().set_world_frame(frame);
+        Ok(())
+    }
+
+    /// Vertical field of view of the active camera in degrees.
+    pub fn set_fov(&self, degrees: f32) {
+        self.with_camera(|cam| cam.lens_mut().fov_y = degrees.clamp(1.0, 179.0).to_radians());
+    }
+
+    /// Fixed near and far planes of the active camera. Disables automatic fitting.
+    pub fn set_clip_planes(&self, near: f32, far: f32) {
+        self.with_camera(|cam| {
+            let lens = cam.lens_mut();
+            lens.near = near.max(1e-4);
+            lens.far = far.max(lens.near * 2.0);
+            lens.auto_clip = false;
+        });
+    }
+
+    /// Fits the near and far planes of all cameras to the scene every frame.
+    pub fn set_auto_clip(&self, enabled: bool) {
+        self.with_cameras(|cam| cam.update_lenses(|lens| lens.auto_clip = enabled));
+    }
+
+    /// Reverse-Z depth with an infinite far plane, avoiding z-fighting in large
+    /// scenes.
+    pub fn set_reverse_z(&self, enabled: bool) {
+        self.with_cameras(|cam| cam.update_lenses(|lens| lens.reverse_z = enabled));
+        self.with_state(|st| st.set_reverse_z(enabled));
+    }
+
+    /// Camera motion response, in rates per second: how fast held keys reach
+    /// full speed, how fast motion stops after release, and how fast orbit
+    /// momentum fades after a drag. Non-positive values disable smoothing.
+    pub fn set_camera_inertia(&self, acceleration: f32, damping: f32, rotation_damping: f32) {
+        self.with_cameras(|cam| {
+            cam.set_inertia(Inertia {
+                acceleration,
+                damping,
+                rotation_damping,
+            })
+        });
+    }
+
+    /// Current key bindings as JSON: `{"MoveForward": ["KeyW"], ...}`.
+    pub fn get_input_bindings(&self) -> String {
+        self.input_map.borrow().to_json()
+    }
+
+    /// Replaces the key bindings from JSON in the format of
+    /// `get_input_bindings`. Actions left out keep their default keys.
+    pub fn set_input_bindings(&self, json: &str) -> Result<(), JsValue> {
+        let map = InputMap::from_json(json).map_err(|e| JsValue::from_str(&e))?;
+        self.set_input_map(map);
+        Ok(())
+    }
+
+    pub fn reset_input_bindings(&self) {
+        self.set_input_map(InputMap::default());
+    }
+
+    /// Stick deflection (0..1) below which gamepad input is ignored.
+    pub fn set_gamepad_deadzone(&self, deadzone: f32) {
+        self.gamepad.borrow_mut().deadzone = deadzone.clamp(0.0, 0.95);
+    }
+
+    /// Inverts the horizontal and/or vertical gamepad look axis.
+    pub fn set_gamepad_invert(&self, invert_x: bool, invert_y: bool) {
+        let mut g = self.gamepad.borrow_mut();
+        g.invert_x = invert_x;
+        g.invert_y = invert_y;
+    }
+
+    pub fn set_gizmo_mode(&self, mode: &str) {
+        self.with_gizmo(|g| match mode {
+            "translate" => g.mode = GizmoMode::Translate,
+            "rotate" => g.mode = GizmoMode::Rotate,
+            "scale" => g.mode = GizmoMode::Scale,
+            _ => {}
+        });
+    }
+
+    pub fn set_gizmo_space(&self, space: &str) {
+        self.with_gizmo(|g| match space {
+            "world" => g.space = GizmoSpace::World,
+            "local" => g.space = GizmoSpace::Local,
+            _ => {}
+        });
+    }
+
+    /// Snapping increments for the transform gizmo; pass 0 to disable one.
+    pub fn set_gizmo_snap(&self, translate: f32, rotate_degrees: f32, scale: f32) {
+        self.with_gizmo(|g| {
+            g.snap.translate = translate;
+            g.snap.rotate = rotate_degrees.to_radians();
+            g.snap.scale = scale;
+        });
+    }
+
+    /// Registers `callback(name, { translation, rotation, scale })`, called
+    /// whenever the transform gizmo changes an object.
+    pub fn on_transform_change(&self, callback: js_sys::Function) {
+        self.gizmo.borrow_mut().on_change = Some(callback);
+    }
+
+    /// Sizes the drawing buffer for a canvas of `width` x `height` device
+    /// pixels. Viewers follow the size of their canvas on their own, so this
+    /// is only needed to override it.
+    pub fn resize(&self, width: u32, height: u32) {
+        self.resolution.device_size.set((width, height));
+        self.resolution.apply();
+    }
+
+    /// Draws at `scale` times the device resolution, e.g. 0.5 for a quarter
+    /// of the pixels on slow GPUs or above 1 for supersampling. Clamped to
+    /// `0.1..=2`.
+    pub fn set_render_scale(&self, scale: f32) {
+        self.resolution.scale.set(scale.clamp(0.1, 2.0));
+        self.resolution.apply();
+    }
+}
+
+impl Drop for Viewer {
+    fn drop(&mut self) {
+        self.dispose();
+    }
+}
+
+/// Page side of a viewer running in a worker: forwards the input and size
+/// of the canvas it was transferred from.
+#[wasm_bindgen]
+pub struct InputForwarder {
+    listeners: Vec<Listener>,
+    size_observer: Option<SizeObserver>,
+}
+
+#[wasm_bindgen]
+impl InputForwarder {
+    #[wasm_bindgen(constructor)]
+    pub fn new(canvas: HtmlCanvasElement, worker: Worker) -> Result<InputForwarder, JsValue> {
+        let window = web_sys::window().unwrap();
+        let listeners = host::forward(&window, &canvas, &worker);
+        let canvas_c = canvas.clone();
+        let size_observer = resize::observe(&canvas, move |width, height| {
+            host::post_size(&worker, &canvas_c, width, height);
+        })?;
+        Ok(InputForwarder {
+            listeners,
+            size_observer: Some(size_observer),
+        })
+    }
 
-    window.request_animation_frame(g.borrow().as_ref().unwrap().as_ref().unchecked_ref())?;
-    Ok(())
+    /// Stops forwarding.
+    pub fn dispose(&mut self) {
+        self.listeners.clear();
+        self.size_observer = None;
+    }
 }
@@ -0,0 +1,41 @@
+// Runs a viewer off the main thread; see worker.html for the page side.
+
+// Patch outdated WebGPU limit name for newer Chrome versions.
+const origRequestDevice = GPUAdapter.prototype.requestDevice;
+GPUAdapter.prototype.requestDevice = function(desc) {
+    if (desc?.requiredLimits?.maxInterStageShaderComponents !== undefined &&
+        desc.requiredLimits.maxInterStageShaderVariables === undefined) {
+        desc.requiredLimits.maxInterStageShaderVariables =
+            desc.requiredLimits.maxInterStageShaderComponents;
+        delete desc.requiredLimits.maxInterStageShaderComponents;
+    }
+    return origRequestDevice.call(this, desc);
+};
+
+import init, { Viewer } from './pkg/webgpu_wasm.js';
+
+let viewer = null;
+// Input forwarded while the viewer is still starting.
+const pending = [];
+
+function handle(data) {
+    if (data.kind === 'call') {
+        viewer[data.method](...data.args);
+    } else {
+        viewer.handle_message(data);
+    }
+}
+
+self.onmessage = async ({ data }) => {
+    if (data.kind === 'init') {
+        await init();
+        viewer = await Viewer.create_offscreen(data.canvas);
+        viewer.set_render_on_demand(true);
+        viewer.set_grid_visible(true);
+        pending.splice(0).forEach(handle);
+    } else if (viewer) {
+        handle(data);
+    } else {
+        pending.push(data);
+    }
+};